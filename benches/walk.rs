@@ -0,0 +1,73 @@
+//! 比较 Linux 上通过目录文件描述符读取与按完整路径读取的扫描速度：
+//!
+//! ```txt
+//! cargo bench --bench walk
+//! ```
+
+use mrdu::struct_define::analysis_context::AnalysisContext;
+use mrdu::struct_define::analysis_item::AnalysisItem;
+use mrdu::struct_define::config::Arguments;
+use mrdu::struct_define::file_info::FileInfo;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use structopt::StructOpt;
+
+/// 每级目录的子目录数、文件数与层数
+const BRANCHING: usize = 3;
+const FILES_PER_DIR: usize = 10;
+const DEPTH: usize = 7;
+const ROUNDS: usize = 5;
+
+fn create_tree(dir: &Path, depth: usize) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(dir)?;
+    for i in 0..FILES_PER_DIR {
+        fs::write(dir.join(format!("file_{}", i)), [0u8; 100])?;
+    }
+    if depth > 0 {
+        for i in 0..BRANCHING {
+            create_tree(&dir.join(format!("dir_{}", i)), depth - 1)?;
+        }
+    }
+    Ok(())
+}
+
+/// 多次扫描中最快的一次
+fn measure(root: &Path, args: &[&str]) -> Result<(Duration, u64), Box<dyn Error>> {
+    let config = Arguments::from_iter(["mrdu"].iter().chain(args));
+    let volume_id = match FileInfo::from_path(root, false)? {
+        FileInfo::Directory { volume_id, .. } => volume_id,
+        _ => return Err("benchmark root is not a directory".into()),
+    };
+    let mut best = Duration::MAX;
+    let mut entries = 0;
+    for _ in 0..ROUNDS {
+        let context = AnalysisContext::new(&config, root, volume_id)?;
+        let start = Instant::now();
+        AnalysisItem::analyze(root, &context)?;
+        best = best.min(start.elapsed());
+        entries = context.progress.files() + context.progress.dirs();
+    }
+    Ok((best, entries))
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let root = std::env::temp_dir().join("mrdu-bench-walk");
+    if !root.exists() {
+        create_tree(&root, DEPTH)?;
+    }
+    for io_mode in ["sequential", "parallel"] {
+        let (portable, entries) = measure(&root, &["--io-mode", io_mode, "--portable-walk"])?;
+        let (fast, _) = measure(&root, &["--io-mode", io_mode])?;
+        println!(
+            "{:<10} {} entries: portable {:?}, fast {:?}, speedup {:.2}x",
+            io_mode,
+            entries,
+            portable,
+            fast,
+            portable.as_secs_f64() / fast.as_secs_f64()
+        );
+    }
+    Ok(())
+}
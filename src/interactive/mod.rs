@@ -0,0 +1,624 @@
+pub mod trash;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::methods::{convert_to_bytes, size_fraction};
+use crate::struct_define::analysis_item::AnalysisItem;
+use crate::struct_define::config::{Arguments, SizeMode};
+use crate::struct_define::display_color::COLOR_GRAY;
+use crate::struct_define::display_info::DisplayItemInfo;
+use crate::struct_define::file_info::FileInfo;
+use crate::struct_define::tree_shape;
+use trash::Trash;
+
+/// 枚举，子项的排序方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Size,
+    Name,
+    /// 按子树中的条目数
+    Count,
+}
+
+impl SortOrder {
+    fn next(self) -> Self {
+        match self {
+            SortOrder::Size => SortOrder::Name,
+            SortOrder::Name => SortOrder::Count,
+            SortOrder::Count => SortOrder::Size,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortOrder::Size => "size",
+            SortOrder::Name => "name",
+            SortOrder::Count => "count",
+        }
+    }
+}
+
+/// 枚举，百分比相对于父目录还是扫描的根目录
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PercentBase {
+    Parent,
+    Root,
+}
+
+/// 枚举，释放空间的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Removal {
+    Delete,
+    /// 移入主目录的回收站
+    Trash,
+}
+
+/// 结构体，交互界面中的一行
+pub struct Row {
+    /// 从扫描根目录到该条目的子项下标
+    pub path: Vec<usize>,
+    /// 按当前百分比基准计算的占比
+    pub percent: f64,
+    pub info: DisplayItemInfo,
+}
+
+/// 结构体，交互界面的状态，与终端的绘制分离
+pub struct Browser<'a> {
+    pub tree: &'a mut AnalysisItem,
+    pub root_path: PathBuf,
+    size_mode: SizeMode,
+    precision: usize,
+    /// 当前进入的子树
+    view: Vec<usize>,
+    expanded: HashSet<Vec<usize>>,
+    pub sort: SortOrder,
+    pub percent_base: PercentBase,
+    pub selected: usize,
+    scroll: usize,
+    rows: Vec<Row>,
+    pub trash: Option<Trash>,
+    /// 由 --import-ncdu 导入的树可能来自其他机器，不允许删除
+    imported: bool,
+    /// 等待确认的操作
+    pub pending: Option<Removal>,
+    /// 上一次操作的结果
+    pub status: Option<String>,
+}
+
+impl<'a> Browser<'a> {
+    pub fn new(tree: &'a mut AnalysisItem, root_path: PathBuf, config: &Arguments) -> Self {
+        let mut browser = Self {
+            tree,
+            root_path,
+            size_mode: config.size_mode(),
+            precision: config.decimal_num,
+            view: Vec::new(),
+            expanded: HashSet::new(),
+            sort: SortOrder::Size,
+            percent_base: PercentBase::Parent,
+            selected: 0,
+            scroll: 0,
+            rows: Vec::new(),
+            trash: Trash::home(),
+            imported: config.import_ncdu.is_some(),
+            pending: None,
+            status: None,
+        };
+        browser.rebuild_rows();
+        browser
+    }
+
+    pub fn item_at(&self, path: &[usize]) -> &AnalysisItem {
+        path.iter().fold(&*self.tree, |item, &index| {
+            &item
+                .children
+                .as_ref()
+                .expect("path points into a directory")[index]
+        })
+    }
+
+    /// 条目在文件系统中的完整路径
+    pub fn path_of(&self, path: &[usize]) -> PathBuf {
+        let mut full_path = self.root_path.clone();
+        let mut item = &*self.tree;
+        for &index in path {
+            item = &item
+                .children
+                .as_ref()
+                .expect("path points into a directory")[index];
+            full_path.push(&item.name);
+        }
+        full_path
+    }
+
+    pub fn rows(&self) -> &[Row] {
+        &self.rows
+    }
+
+    pub fn view(&self) -> &[usize] {
+        &self.view
+    }
+
+    pub fn selected_row(&self) -> Option<&Row> {
+        self.rows.get(self.selected)
+    }
+
+    /// 按当前排序方式排列的子项下标
+    fn sorted_children(&self, item: &AnalysisItem) -> Vec<usize> {
+        let children = match &item.children {
+            Some(children) => children,
+            None => return Vec::new(),
+        };
+        let mut indices = (0..children.len()).collect::<Vec<_>>();
+        match self.sort {
+            SortOrder::Size => {
+                indices.sort_by_key(|&i| std::cmp::Reverse(children[i].size(self.size_mode)))
+            }
+            SortOrder::Name => indices.sort_by(|&a, &b| children[a].name.cmp(&children[b].name)),
+            SortOrder::Count => indices.sort_by_key(|&i| {
+                std::cmp::Reverse(children[i].file_count + children[i].dir_count)
+            }),
+        }
+        indices
+    }
+
+    /// 重新展开可见的条目，展开或排序方式改变后调用
+    pub fn rebuild_rows(&mut self) {
+        let mut rows = Vec::new();
+        let view = self.view.clone();
+        self.push_rows(&view, &DisplayItemInfo::new(), &mut rows);
+        self.rows = rows;
+        self.selected = self.selected.min(self.rows.len().saturating_sub(1));
+    }
+
+    fn push_rows(&self, path: &[usize], info: &DisplayItemInfo, rows: &mut Vec<Row>) {
+        let item = self.item_at(path);
+        let indices = self.sorted_children(item);
+        let count = indices.len();
+        for (position, index) in indices.into_iter().enumerate() {
+            let child = &item.children.as_ref().unwrap()[index];
+            let percent = match self.percent_base {
+                PercentBase::Parent => size_fraction(child, item, self.size_mode),
+                PercentBase::Root => size_fraction(child, self.tree, self.size_mode),
+            };
+            let mut child_path = path.to_vec();
+            child_path.push(index);
+            let child_info = info.add_item(percent, position + 1 == count);
+            let expanded = self.expanded.contains(&child_path);
+            rows.push(Row {
+                path: child_path.clone(),
+                percent,
+                info: child_info.clone(),
+            });
+            if expanded {
+                self.push_rows(&child_path, &child_info, rows);
+            }
+        }
+    }
+
+    pub fn move_selection(&mut self, offset: isize) {
+        let last = self.rows.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + offset).clamp(0, last) as usize;
+    }
+
+    /// 展开或折叠选中的目录
+    pub fn toggle_expanded(&mut self) {
+        if let Some(row) = self.selected_row() {
+            let path = row.path.clone();
+            if self.item_at(&path).is_dir() && !self.expanded.remove(&path) {
+                self.expanded.insert(path);
+            }
+            self.rebuild_rows();
+        }
+    }
+
+    /// 进入选中的目录，使其成为显示的根
+    pub fn enter(&mut self) {
+        if let Some(row) = self.selected_row() {
+            let path = row.path.clone();
+            if self.item_at(&path).is_dir() {
+                self.view = path;
+                self.selected = 0;
+                self.scroll = 0;
+                self.rebuild_rows();
+            }
+        }
+    }
+
+    /// 回到上一级目录，并选中刚才所在的目录
+    pub fn leave(&mut self) {
+        if let Some(previous) = self.view.pop() {
+            self.scroll = 0;
+            self.rebuild_rows();
+            let mut path = self.view.clone();
+            path.push(previous);
+            self.selected = self
+                .rows
+                .iter()
+                .position(|row| row.path == path)
+                .unwrap_or(0);
+        }
+    }
+
+    pub fn cycle_sort(&mut self) {
+        self.sort = self.sort.next();
+        self.rebuild_rows();
+    }
+
+    pub fn toggle_percent_base(&mut self) {
+        self.percent_base = match self.percent_base {
+            PercentBase::Parent => PercentBase::Root,
+            PercentBase::Root => PercentBase::Parent,
+        };
+        self.rebuild_rows();
+    }
+
+    /// 请求删除选中的条目或将其移入回收站，需确认后才会执行
+    pub fn request_removal(&mut self, removal: Removal) {
+        let Some(row) = self.selected_row() else {
+            return;
+        };
+        if self.imported {
+            self.status = Some("Removal is disabled for imported trees".to_string());
+            return;
+        }
+        if let Some(reason) = removal_blocker(self.item_at(&row.path)) {
+            self.status = Some(format!("Refusing to remove: {}", reason));
+            return;
+        }
+        self.status = None;
+        self.pending = Some(removal);
+    }
+
+    /// 确认提示，包含将释放的空间
+    pub fn confirmation(&self) -> Option<String> {
+        let removal = self.pending?;
+        let row = self.selected_row()?;
+        let action = match removal {
+            Removal::Delete => "Delete",
+            Removal::Trash => "Move to trash",
+        };
+        Some(format!(
+            "{} {}, freeing {}? [y/N]",
+            action,
+            self.path_of(&row.path).display(),
+            format_size(self.item_at(&row.path), self.size_mode)
+        ))
+    }
+
+    pub fn cancel_removal(&mut self) {
+        self.pending = None;
+    }
+
+    /// 执行已确认的操作，成功后从树中移除该条目并更新各级目录的大小，返回释放的字节数
+    pub fn confirm_removal(&mut self) -> io::Result<u64> {
+        let (Some(removal), Some(row)) = (self.pending.take(), self.selected_row()) else {
+            return Ok(0);
+        };
+        let path = row.path.clone();
+        let full_path = self.path_of(&path);
+        match removal {
+            Removal::Delete => delete_path(&full_path)?,
+            Removal::Trash => {
+                let trash = self.trash.as_ref().ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotFound, "cannot locate the home trash")
+                })?;
+                trash.put(&full_path)?;
+            }
+        }
+        let removed = self
+            .tree
+            .remove_descendant(&path)
+            .expect("selected row is in the tree");
+        self.forget_removed(&path);
+        self.rebuild_rows();
+        Ok(removed.size(self.size_mode))
+    }
+
+    /// 移除条目后，调整其后兄弟条目及其后代在展开集合中的下标
+    fn forget_removed(&mut self, removed: &[usize]) {
+        let (&index, parent) = removed.split_last().expect("rows are below the root");
+        self.expanded = self
+            .expanded
+            .drain()
+            .filter_map(|mut path| {
+                if path.len() > parent.len() && path.starts_with(parent) {
+                    match path[parent.len()].cmp(&index) {
+                        Ordering::Equal => return None,
+                        Ordering::Greater => path[parent.len()] -= 1,
+                        Ordering::Less => {}
+                    }
+                }
+                Some(path)
+            })
+            .collect();
+    }
+
+    /// 根据终端高度调整滚动位置，使选中的行可见
+    fn scroll_to_selected(&mut self, height: usize) {
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if height > 0 && self.selected >= self.scroll + height {
+            self.scroll = self.selected + 1 - height;
+        }
+    }
+}
+
+/// 函数，在终端中以全屏界面浏览分析结果
+pub fn run_interactive(
+    tree: &mut AnalysisItem,
+    root_path: &Path,
+    config: &Arguments,
+) -> io::Result<()> {
+    let mut browser = Browser::new(tree, root_path.to_path_buf(), config);
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    let result = event_loop(&mut browser, &mut stdout);
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn event_loop<W: Write>(browser: &mut Browser, out: &mut W) -> io::Result<()> {
+    loop {
+        let (width, height) = terminal::size()?;
+        // 首行显示当前目录，末行显示按键说明
+        let body_height = (height as usize).saturating_sub(2);
+        browser.scroll_to_selected(body_height);
+        draw(browser, out, width as usize, body_height)?;
+
+        let key = match event::read()? {
+            Event::Key(
+                key @ KeyEvent {
+                    kind: KeyEventKind::Press,
+                    ..
+                },
+            ) => key,
+            _ => continue,
+        };
+        // 确认提示只接受 y，其他按键均取消
+        if browser.pending.is_some() {
+            browser.status = match key.code {
+                KeyCode::Char('y') | KeyCode::Char('Y') => Some(match browser.confirm_removal() {
+                    Ok(size) => format!("Freed {}", convert_to_bytes(size as f64)),
+                    Err(error) => format!("Error: {}", error),
+                }),
+                _ => None,
+            };
+            browser.cancel_removal();
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Up | KeyCode::Char('k') => browser.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => browser.move_selection(1),
+            KeyCode::PageUp => browser.move_selection(-(body_height as isize)),
+            KeyCode::PageDown => browser.move_selection(body_height as isize),
+            KeyCode::Home => browser.move_selection(isize::MIN / 2),
+            KeyCode::End => browser.move_selection(isize::MAX / 2),
+            KeyCode::Char(' ') | KeyCode::Tab => browser.toggle_expanded(),
+            KeyCode::Enter | KeyCode::Right | KeyCode::Char('l') => browser.enter(),
+            KeyCode::Backspace | KeyCode::Left | KeyCode::Char('h') => browser.leave(),
+            KeyCode::Char('s') => browser.cycle_sort(),
+            KeyCode::Char('p') => browser.toggle_percent_base(),
+            KeyCode::Char('d') | KeyCode::Delete => browser.request_removal(Removal::Delete),
+            KeyCode::Char('t') => browser.request_removal(Removal::Trash),
+            _ => {}
+        }
+    }
+}
+
+fn to_crossterm_color(color: Option<termcolor::Color>) -> Color {
+    match color {
+        Some(termcolor::Color::Rgb(r, g, b)) => Color::Rgb { r, g, b },
+        _ => Color::Reset,
+    }
+}
+
+fn draw<W: Write>(
+    browser: &Browser,
+    out: &mut W,
+    width: usize,
+    body_height: usize,
+) -> io::Result<()> {
+    queue!(out, Clear(ClearType::All), MoveTo(0, 0))?;
+
+    let view_item = browser.item_at(browser.view());
+    let header = format!(
+        " {} {}  sort: {}  percent of: {}",
+        browser.path_of(browser.view()).display(),
+        format_size(view_item, browser.size_mode),
+        browser.sort.label(),
+        match browser.percent_base {
+            PercentBase::Parent => "parent",
+            PercentBase::Root => "root",
+        }
+    );
+    queue!(
+        out,
+        SetAttribute(Attribute::Reverse),
+        Print(truncate(&header, width)),
+        SetAttribute(Attribute::Reset)
+    )?;
+
+    let gray = to_crossterm_color(COLOR_GRAY);
+    for (line, (index, row)) in browser
+        .rows()
+        .iter()
+        .enumerate()
+        .skip(browser.scroll)
+        .take(body_height)
+        .enumerate()
+    {
+        let item = browser.item_at(&row.path);
+        let info = &row.info;
+        let marker = match (&item.children, browser.expanded.contains(&row.path)) {
+            (Some(children), false) if !children.is_empty() => "▸ ",
+            (Some(children), true) if !children.is_empty() => "▾ ",
+            _ => "  ",
+        };
+        let selected = index == browser.selected;
+        queue!(out, MoveTo(0, line as u16 + 1))?;
+        if selected {
+            queue!(out, SetAttribute(Attribute::Reverse))?;
+        }
+        // 与文本输出相同的分段与配色
+        let segments = [
+            (
+                format!("{}{}", info.prefix, info.display_prefix(true)),
+                gray,
+            ),
+            (
+                format!(
+                    " {:w$.p$}% ",
+                    info.occupied_size,
+                    w = browser.precision + 3,
+                    p = browser.precision
+                ),
+                to_crossterm_color(info.display_color(false)),
+            ),
+            (
+                format_size(item, browser.size_mode),
+                to_crossterm_color(info.display_color(true)),
+            ),
+            (format!(" {} ", tree_shape::SPACING), gray),
+            (
+                format!("{}{}", marker, item.name.to_string_lossy()),
+                Color::Reset,
+            ),
+            (describe_link(item), gray),
+        ];
+        let mut remaining = width;
+        for (text, color) in segments {
+            let text = truncate(&text, remaining);
+            remaining -= text.chars().count();
+            queue!(out, SetForegroundColor(color), Print(text))?;
+        }
+        queue!(out, ResetColor, SetAttribute(Attribute::Reset))?;
+    }
+
+    // 末行依次显示确认提示、上一次操作的结果或按键说明
+    let footer = match (browser.confirmation(), &browser.status) {
+        (Some(confirmation), _) => (format!(" {}", confirmation), Color::Yellow),
+        (None, Some(status)) => (format!(" {}", status), Color::Reset),
+        (None, None) => (
+            " ↑/↓ move  space expand  enter/→ open  ←/backspace up  s sort  p percent  \
+             d delete  t trash  q quit"
+                .to_string(),
+            gray,
+        ),
+    };
+    queue!(
+        out,
+        MoveTo(0, body_height as u16 + 1),
+        SetForegroundColor(footer.1),
+        Print(truncate(&footer.0, width)),
+        ResetColor
+    )?;
+    out.flush()
+}
+
+/// 函数，条目中有未计入将释放的空间的内容时返回原因。
+/// 挂载在其中的其他文件系统、被过滤或无法读取的条目都会被一并删除，却未显示在确认提示中；
+/// 被跟随的符号链接计入了目标的大小，删除时却只删除链接本身
+fn removal_blocker(item: &AnalysisItem) -> Option<&'static str> {
+    if let Some(symlink) = &item.symlink {
+        return symlink
+            .followed
+            .then_some("it is or contains a followed symlink whose target would be kept");
+    }
+    if item.mount.is_some() {
+        return Some("it is or contains a mount point");
+    }
+    if item.filtered || item.partial || !item.errors.is_empty() {
+        return Some("it contains entries that were filtered out or could not be read");
+    }
+    item.children.iter().flatten().find_map(removal_blocker)
+}
+
+/// 函数，删除文件或整个目录，符号链接只删除链接本身。
+/// 目录逐层删除，遇到其他文件系统的目录时停止
+fn delete_path(path: &Path) -> io::Result<()> {
+    match file_info(path)? {
+        FileInfo::Directory { volume_id, .. } => delete_dir(path, volume_id),
+        _ => fs::remove_file(path),
+    }
+}
+
+fn delete_dir(dir: &Path, volume_id: u64) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        match file_info(&path)? {
+            FileInfo::Directory {
+                volume_id: child_volume,
+                ..
+            } if child_volume != volume_id => {
+                return Err(io::Error::other(format!(
+                    "{} is on another filesystem",
+                    path.display()
+                )))
+            }
+            FileInfo::Directory { .. } => delete_dir(&path, volume_id)?,
+            _ => fs::remove_file(&path)?,
+        }
+    }
+    fs::remove_dir(dir)
+}
+
+/// 函数，不跟随符号链接读取条目信息
+fn file_info(path: &Path) -> io::Result<FileInfo> {
+    FileInfo::from_path(path, false).map_err(|error| io::Error::other(error.to_string()))
+}
+
+/// 函数，按大小类型格式化条目的大小
+fn format_size(item: &AnalysisItem, size_mode: SizeMode) -> String {
+    match size_mode {
+        SizeMode::Apparent => format!("[{}]", convert_to_bytes(item.apparent_size as f64)),
+        SizeMode::Allocated => format!("[{}]", convert_to_bytes(item.allocated_size as f64)),
+        // 实际占用 | 文件长度
+        SizeMode::Both => format!(
+            "[{} | {}]",
+            convert_to_bytes(item.allocated_size as f64),
+            convert_to_bytes(item.apparent_size as f64)
+        ),
+    }
+}
+
+/// 函数，符号链接、挂载点与未完整扫描的附加说明
+fn describe_link(item: &AnalysisItem) -> String {
+    let mut text = String::new();
+    if let Some(symlink) = &item.symlink {
+        text.push_str(&format!(" -> {}", symlink.target.display()));
+        if symlink.cycle {
+            text.push_str(" (cycle, not followed)");
+        }
+        if symlink.duplicate {
+            text.push_str(" (already counted, not followed)");
+        }
+    }
+    if let Some(mount) = &item.mount {
+        match mount.scanned {
+            true => text.push_str(&format!(" [mount: {}]", mount.source)),
+            false => text.push_str(&format!(" [mount: {}, not scanned]", mount.source)),
+        }
+    }
+    if item.partial {
+        text.push_str(" (partial)");
+    }
+    text
+}
+
+/// 函数，按终端宽度截断一行
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
@@ -0,0 +1,158 @@
+//! 按 freedesktop.org Trash 规范将条目移入主目录的回收站：
+//! 文件移动到 `$XDG_DATA_HOME/Trash/files`（默认 `~/.local/share/Trash`），
+//! 并在 `info` 中写入同名的 `.trashinfo`，记录原路径与删除时间，供文件管理器还原。
+
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::methods::format_local_time;
+
+/// 结构体，回收站目录
+#[derive(Debug, Clone)]
+pub struct Trash {
+    dir: PathBuf,
+}
+
+impl Trash {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// 当前用户主目录下的回收站，无法确定主目录时返回 None
+    pub fn home() -> Option<Self> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local/share"))
+            })?;
+        Some(Self::new(data_home.join("Trash")))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 将 path 移入回收站，返回其在回收站中的位置
+    #[cfg(unix)]
+    pub fn put(&self, path: &Path) -> io::Result<PathBuf> {
+        let path = absolute_path(path)?;
+        let name = path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "cannot trash a root"))?;
+        let files_dir = self.dir.join("files");
+        let info_dir = self.dir.join("info");
+        create_private_dir(&files_dir)?;
+        create_private_dir(&info_dir)?;
+
+        // 先以 create_new 占用 .trashinfo 的名字，避免与其他程序同时放入同名条目
+        let mut number = 1;
+        let (trashed, info_path) = loop {
+            let mut candidate = OsString::from(name);
+            if number > 1 {
+                candidate.push(format!(".{}", number));
+            }
+            number += 1;
+            let mut info_name = candidate.clone();
+            info_name.push(".trashinfo");
+            let info_path = info_dir.join(info_name);
+            let mut info_file = match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&info_path)
+            {
+                Ok(file) => file,
+                Err(error) if error.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(error) => return Err(error),
+            };
+            let trashed = files_dir.join(&candidate);
+            // 没有 .trashinfo 的残留文件
+            if fs::symlink_metadata(&trashed).is_ok() {
+                drop(info_file);
+                fs::remove_file(&info_path)?;
+                continue;
+            }
+            let written = write!(
+                info_file,
+                "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                encode_path(&path),
+                deletion_date()
+            );
+            if let Err(error) = written {
+                let _ = fs::remove_file(&info_path);
+                return Err(error);
+            }
+            break (trashed, info_path);
+        };
+
+        fs::rename(&path, &trashed).map_err(|error| {
+            let _ = fs::remove_file(&info_path);
+            match error.kind() {
+                io::ErrorKind::CrossesDevices => io::Error::new(
+                    error.kind(),
+                    "the trash is on another filesystem, delete it instead",
+                ),
+                _ => error,
+            }
+        })?;
+        Ok(trashed)
+    }
+
+    #[cfg(not(unix))]
+    pub fn put(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "the freedesktop trash is only available on Unix",
+        ))
+    }
+}
+
+/// 函数，取得绝对路径，但不解析最后一级的符号链接
+#[cfg(unix)]
+fn absolute_path(path: &Path) -> io::Result<PathBuf> {
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) if !parent.as_os_str().is_empty() => {
+            Ok(parent.canonicalize()?.join(name))
+        }
+        (_, Some(name)) => Ok(std::env::current_dir()?.join(name)),
+        _ => path.canonicalize(),
+    }
+}
+
+#[cfg(unix)]
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    use std::os::unix::fs::DirBuilderExt;
+    fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)
+}
+
+/// 函数，按 URL 的规则转义路径中的字节
+#[cfg(unix)]
+fn encode_path(path: &Path) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    let bytes = path.as_os_str().as_bytes();
+    let mut encoded = String::with_capacity(bytes.len());
+    for &byte in bytes {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// 函数，本地时间的删除时间
+#[cfg(unix)]
+fn deletion_date() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    format_local_time(now)
+}
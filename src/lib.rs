@@ -1,2 +1,5 @@
+pub mod interactive;
 pub mod methods;
+pub mod output;
 pub mod struct_define;
+pub mod watch;
@@ -1,42 +1,543 @@
 use atty::Stream;
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
 use std::env;
 use std::error::Error;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
 use structopt::StructOpt;
 use termcolor::{BufferWriter, ColorChoice};
 
-use mrdu::methods::show_disk_analyze_result;
+use mrdu::interactive::run_interactive;
+use mrdu::methods::{
+    convert_to_bytes, format_local_time, show_diff_result, show_disk_analyze_result,
+};
+use mrdu::output::delimited::{write_delimited, Delimited};
+use mrdu::output::dupes::write_dupes_json;
+use mrdu::output::json::write_json;
+use mrdu::output::ncdu::{read_ncdu, write_ncdu};
+use mrdu::output::snapshot::{read_snapshot, write_snapshot};
+use mrdu::struct_define::analysis_context::AnalysisContext;
 use mrdu::struct_define::analysis_item::AnalysisItem;
-use mrdu::struct_define::config::Arguments;
+use mrdu::struct_define::compact_tree::CompactTree;
+use mrdu::struct_define::config::{
+    Arguments, Command, IoMode, OutputFormat, SizeMode, SnapshotCommand, SymlinkPolicy,
+};
 use mrdu::struct_define::display_info::DisplayItemInfo;
+use mrdu::struct_define::duplicates::{find_duplicates, DuplicateSet};
+use mrdu::struct_define::file_age::{AgeStats, AgeTime};
 use mrdu::struct_define::file_info::FileInfo;
+use mrdu::struct_define::file_types::{FileTypeMode, FileTypeStats, UsageGroup};
+use mrdu::struct_define::ignore_rules::IgnoreMode;
+use mrdu::struct_define::interrupt::Interrupt;
+use mrdu::struct_define::owners::{OwnerKind, OwnerStats};
+use mrdu::struct_define::size_diff::SizeDiff;
+use mrdu::struct_define::top_entries::TopEntries;
+use mrdu::struct_define::tree_shape;
+use mrdu::watch::{run_watch, LiveTree};
 
 fn main() -> Result<(), Box<dyn Error>> {
     let test_args = Arguments::from_args();
-    let current_dir = env::current_dir()?;
-    let target_dir = test_args.target_dir.as_ref().unwrap_or(&current_dir);
-    let file_info = FileInfo::from_path(&target_dir, test_args.apparent)?;
+    // 第一次 Ctrl-C 停止扫描并输出已读取的部分
+    let interrupt = Interrupt::new();
+    interrupt.install_handler()?;
+    if test_args.compact && (test_args.command.is_some() || test_args.output != OutputFormat::Text)
+    {
+        return Err("--compact supports only the text tree output".into());
+    }
+    if test_args.watch && (test_args.command.is_some() || test_args.output != OutputFormat::Text) {
+        return Err("--watch supports only the text tree output".into());
+    }
+    if let Some(command) = &test_args.command {
+        return run_command(command, &test_args, &interrupt);
+    }
+    // 导出到 stdout 时不再输出其他内容
+    let export_to_stdout = test_args
+        .export_ncdu
+        .as_ref()
+        .is_some_and(|file| file.as_os_str() == "-");
+    if test_args.interactive && !atty::is(Stream::Stdout) {
+        return Err("interactive mode requires a terminal".into());
+    }
+    // 机器可读的格式只向 stdout 输出结果本身
+    let text_output =
+        test_args.output == OutputFormat::Text && !export_to_stdout && !test_args.interactive;
 
-    let color_choice = if atty::is(Stream::Stdout) {
+    let start_time = std::time::Instant::now();
+    let (target_dir, mut analysed, context) = match &test_args.import_ncdu {
+        Some(file) => {
+            let (root, analysed) = match file.as_os_str() == "-" {
+                true => read_ncdu(io::stdin().lock(), test_args.size_mode())?,
+                false => read_ncdu(File::open(file)?, test_args.size_mode())?,
+            };
+            if text_output {
+                print_header("Imported", &root, &test_args);
+            }
+            (root, analysed, None)
+        }
+        None => {
+            let current_dir = env::current_dir()?;
+            let target_dir = test_args.target_dir.clone().unwrap_or(current_dir);
+            if text_output {
+                print_header("Analyzing", &target_dir, &test_args);
+            }
+            let (analysed, mut context) = scan(&target_dir, &test_args, &interrupt)?;
+            if test_args.watch {
+                let live = LiveTree::new(analysed, &target_dir, context, &test_args);
+                return interrupt.catch(|| run_watch(live, &interrupt, color_choice()));
+            }
+            // 只还原需要显示的部分
+            let analysed = match context.compact.take() {
+                Some(compact) => {
+                    let tree = compact.finish(analysed);
+                    if text_output {
+                        print_compact_usage(&tree);
+                    }
+                    tree.to_item(&test_args)
+                }
+                None => analysed,
+            };
+            (target_dir, analysed, Some(context))
+        }
+    };
+
+    if let Some(file) = &test_args.export_ncdu {
+        match export_to_stdout {
+            true => write_ncdu(&analysed, &target_dir, &mut io::stdout().lock())?,
+            false => write_ncdu(
+                &analysed,
+                &target_dir,
+                &mut io::BufWriter::new(File::create(file)?),
+            )?,
+        }
+    }
+
+    match test_args.output {
+        _ if export_to_stdout => {}
+        _ if test_args.interactive => run_interactive(&mut analysed, &target_dir, &test_args)?,
+        OutputFormat::Text => {
+            let stdout = BufferWriter::stdout(color_choice());
+            let mut buffer = stdout.buffer();
+            show_disk_analyze_result(&analysed, &test_args, &DisplayItemInfo::new(), &mut buffer)?;
+            stdout.print(&buffer)?;
+            if let Some(context) = &context {
+                print_top_entries("Largest files", &context.top_files);
+                print_top_entries("Largest directories by direct content", &context.top_dirs);
+                print_file_types(&context.file_types, &test_args);
+                print_owners(&context.owners, &test_args);
+                print_owners(&context.groups, &test_args);
+                print_age_histogram(&context.ages, &test_args);
+            }
+            print_summary(
+                &analysed,
+                context.as_ref(),
+                &test_args,
+                start_time.elapsed(),
+            );
+        }
+        OutputFormat::Json => {
+            write_json(&analysed, &target_dir, &test_args, &mut io::stdout().lock())?
+        }
+        OutputFormat::Csv | OutputFormat::Tsv => {
+            let format = match test_args.output {
+                OutputFormat::Csv => Delimited::Csv,
+                _ => Delimited::Tsv,
+            };
+            let mut stdout = io::BufWriter::new(io::stdout().lock());
+            write_delimited(&analysed, &target_dir, &test_args, format, &mut stdout)?;
+        }
+    }
+    if test_args.show_errors {
+        for error in analysed.collect_errors() {
+            eprintln!("mrdu: {}", error);
+        }
+    }
+    Ok(())
+}
+
+/// 函数，执行子命令
+fn run_command(
+    command: &Command,
+    config: &Arguments,
+    interrupt: &Interrupt,
+) -> Result<(), Box<dyn Error>> {
+    match command {
+        Command::Snapshot(SnapshotCommand::Save { file }) => {
+            let target_dir = match &config.target_dir {
+                Some(target_dir) => target_dir.clone(),
+                None => env::current_dir()?,
+            };
+            let (analysed, _) = scan(&target_dir, config, interrupt)?;
+            match file.as_os_str() == "-" {
+                true => write_snapshot(&analysed, &target_dir, &mut io::stdout().lock())?,
+                false => {
+                    let mut writer = io::BufWriter::new(File::create(file)?);
+                    write_snapshot(&analysed, &target_dir, &mut writer)?;
+                    println!(
+                        "Saved snapshot of {} to {}",
+                        target_dir.display(),
+                        file.display()
+                    );
+                }
+            }
+        }
+        Command::Dupes { dir, min_size } => {
+            let target_dir = match dir.as_ref().or(config.target_dir.as_ref()) {
+                Some(target_dir) => target_dir.clone(),
+                None => env::current_dir()?,
+            };
+            let (analysed, _) = scan(&target_dir, config, interrupt)?;
+            // 与扫描使用相同数量的线程读取文件
+            let pool = scan_pool(config)?;
+            let find =
+                || find_duplicates(&analysed, &target_dir, *min_size, config.io_mode, interrupt);
+            let sets = interrupt.catch(|| match &pool {
+                Some(pool) => pool.install(find),
+                None => find(),
+            });
+            if interrupt.is_triggered() {
+                eprintln!("mrdu: interrupted, showing the duplicates confirmed so far");
+            }
+            match config.output {
+                OutputFormat::Text => print_duplicates(&sets),
+                OutputFormat::Json => {
+                    write_dupes_json(&sets, &target_dir, &mut io::stdout().lock())?
+                }
+                OutputFormat::Csv | OutputFormat::Tsv => {
+                    return Err("dupes supports only text and json output".into())
+                }
+            }
+        }
+        Command::Diff { old, new } => {
+            let old = read_snapshot(File::open(old)?, config.size_mode())?;
+            // 未指定新快照时重新扫描旧快照的目录
+            let (new_root, new_time, new_tree) = match new {
+                Some(file) => {
+                    let new = read_snapshot(File::open(file)?, config.size_mode())?;
+                    (new.root, format_local_time(new.timestamp), new.tree)
+                }
+                None => {
+                    let (analysed, _) = scan(&old.root, config, interrupt)?;
+                    (old.root.clone(), "now".to_string(), analysed)
+                }
+            };
+            println!(
+                "\nComparing: {} ({}) -> {} ({})",
+                old.root.display(),
+                format_local_time(old.timestamp),
+                new_root.display(),
+                new_time
+            );
+            let diff = SizeDiff::compare(Some(&old.tree), Some(&new_tree), config.size_mode());
+            let stdout = BufferWriter::stdout(color_choice());
+            let mut buffer = stdout.buffer();
+            show_diff_result(
+                &diff,
+                config,
+                &DisplayItemInfo::new(),
+                diff.churn(),
+                &mut buffer,
+            )?;
+            stdout.print(&buffer)?;
+        }
+    }
+    Ok(())
+}
+
+/// 函数，按可释放的字节数降序输出重复文件
+fn print_duplicates(sets: &[DuplicateSet]) {
+    let wasted = sets.iter().map(DuplicateSet::wasted).sum::<u64>();
+    println!(
+        "\nDuplicates: {} sets, {} wasted",
+        sets.len(),
+        convert_to_bytes(wasted as f64)
+    );
+    for set in sets {
+        println!(
+            "\n[{} x {}, {} wasted]",
+            convert_to_bytes(set.size as f64),
+            set.paths.len(),
+            convert_to_bytes(set.wasted() as f64)
+        );
+        for path in &set.paths {
+            println!("  {}", path.display());
+        }
+    }
+}
+
+/// 函数，只在终端中输出颜色
+fn color_choice() -> ColorChoice {
+    if atty::is(Stream::Stdout) {
         ColorChoice::Auto
     } else {
         ColorChoice::Never
-    };
+    }
+}
 
-    let stdout = BufferWriter::stdout(color_choice);
-    let mut buffer = stdout.buffer();
+/// 扫描线程的栈大小，扫描按目录层级递归，默认的 2 MiB 会限制能够扫描的深度
+const SCAN_STACK_SIZE: usize = 64 * 1024 * 1024;
 
-    println!("\nAnalyzing: {}", target_dir.display());
+/// 函数，按 --threads 创建读取文件系统的线程池，逐个读取时不需要
+fn scan_pool(config: &Arguments) -> Result<Option<ThreadPool>, ThreadPoolBuildError> {
+    match config.io_mode {
+        IoMode::Sequential => Ok(None),
+        IoMode::Parallel => ThreadPoolBuilder::new()
+            .num_threads(config.scan_threads())
+            .stack_size(SCAN_STACK_SIZE)
+            .thread_name(|index| format!("mrdu-scan-{}", index))
+            .build()
+            .map(Some),
+    }
+}
 
-    let start_time = std::time::Instant::now();
-    let analysed = match file_info {
-        FileInfo::Directory { volume_id } => {
-            AnalysisItem::analyze(&target_dir, test_args.apparent, volume_id)?
+/// 函数，扫描目标目录
+fn scan(
+    target_dir: &Path,
+    config: &Arguments,
+    interrupt: &Interrupt,
+) -> Result<(AnalysisItem, AnalysisContext), Box<dyn Error>> {
+    let file_info =
+        FileInfo::from_path(target_dir, config.symlink_policy() == SymlinkPolicy::Follow)?;
+    let mut context = match file_info {
+        FileInfo::Directory { volume_id, .. } => {
+            AnalysisContext::new(config, target_dir, volume_id)?
         }
         _ => return Err(format!("{} is not a directory!", target_dir.display()).into()),
     };
-    show_disk_analyze_result(&analysed, &test_args, &DisplayItemInfo::new(), &mut buffer)?;
-    stdout.print(&buffer)?;
-    let elapsed_time = start_time.elapsed();
-    println!("\nElapsed time: {:?}", elapsed_time);
-    Ok(())
+    context.interrupt = interrupt.clone();
+    let pool = scan_pool(config)?;
+    // 进度行写入 stderr，重定向时不显示
+    let analysed = interrupt.catch(|| {
+        thread::scope(|scope| {
+            let (stop, stopped) = mpsc::channel();
+            if atty::is(Stream::Stderr) {
+                scope.spawn(|| context.progress.report(stopped));
+            }
+            let analysed = match &pool {
+                // 返回的错误需要在线程间传递
+                Some(pool) => pool
+                    .install(|| {
+                        AnalysisItem::analyze(target_dir, &context)
+                            .map_err(|error| error.to_string())
+                    })
+                    .map_err(Into::into),
+                None => AnalysisItem::analyze(target_dir, &context),
+            };
+            drop(stop);
+            analysed
+        })
+    })?;
+    if interrupt.is_triggered() {
+        eprintln!("mrdu: scan interrupted, showing partial results");
+    }
+    // 缓存无法写入时仍然输出结果
+    if let Some(cache) = &context.cache {
+        if let Err(error) = cache.save() {
+            eprintln!(
+                "mrdu: could not write cache {}: {}",
+                cache.file().display(),
+                error
+            );
+        }
+    }
+    Ok((analysed, context))
+}
+
+/// 函数，输出 --compact 的 arena 占用的内存
+fn print_compact_usage(tree: &CompactTree) {
+    println!(
+        "Compact tree: {} entries in {}",
+        tree.node_count(),
+        convert_to_bytes(tree.memory_usage() as f64)
+    );
+}
+
+/// 函数，在树形结构之前输出分析的目录
+fn print_header(action: &str, root: &Path, config: &Arguments) {
+    println!("\n{}: {}", action, root.display());
+    if config.size_mode() == SizeMode::Both {
+        println!("Sizes: [allocated | apparent]");
+    }
+}
+
+/// 函数，按大小降序输出扫描过程中记录的最大条目
+fn print_top_entries(title: &str, entries: &TopEntries) {
+    if !entries.is_enabled() {
+        return;
+    }
+    println!("\n{}:", title);
+    for (rank, (size, path)) in entries.sorted().iter().enumerate() {
+        println!(
+            "{:>4}. [{}] {}",
+            rank + 1,
+            convert_to_bytes(*size as f64),
+            path.display()
+        );
+    }
+}
+
+/// 函数，按类型输出文件的总大小、数量与占比
+fn print_file_types(stats: &FileTypeStats, config: &Arguments) {
+    let title = match stats.mode {
+        FileTypeMode::Off => return,
+        FileTypeMode::Extension => "Usage by extension",
+        FileTypeMode::Content => "Usage by content type",
+    };
+    println!("\n{}:", title);
+    print_usage_groups(&stats.sorted(), config, "");
+}
+
+/// 函数，按所有者或用户组输出文件的总大小，可按顶层目录分别输出
+fn print_owners(stats: &OwnerStats, config: &Arguments) {
+    if !stats.is_enabled() {
+        return;
+    }
+    let kind = match stats.kind {
+        OwnerKind::User => "owner",
+        OwnerKind::Group => "group",
+    };
+    println!("\nUsage by {}:", kind);
+    print_usage_groups(&stats.totals(), config, "");
+    if stats.per_directory() {
+        println!("\nUsage by {} per top-level directory:", kind);
+        for (dir, owners) in stats.directories() {
+            println!("{}", Path::new(&dir).display());
+            print_usage_groups(&owners, config, "  ");
+        }
+    }
+}
+
+/// 函数，按大小降序输出各分组的总大小、数量与占比，低于 --min-percent 的分组合并为一行
+fn print_usage_groups(groups: &[(String, UsageGroup)], config: &Arguments, indent: &str) {
+    let total = groups.iter().map(|(_, group)| group.size).sum::<u64>();
+    let percent = |size: u64| match total {
+        0 => 0.0,
+        _ => 100.0 * size as f64 / total as f64,
+    };
+    let (shown, hidden): (Vec<_>, Vec<_>) = groups
+        .iter()
+        .partition(|(_, group)| percent(group.size) > config.min_percent);
+
+    for (name, group) in &shown {
+        print_usage_line(indent, percent(group.size), name, group, config);
+    }
+    if !hidden.is_empty() {
+        let other = UsageGroup {
+            size: hidden.iter().map(|(_, group)| group.size).sum(),
+            count: hidden.iter().map(|(_, group)| group.count).sum(),
+        };
+        let name = format!("{} more below {}%", hidden.len(), config.min_percent);
+        print_usage_line(indent, percent(other.size), &name, &other, config);
+    }
+}
+
+/// 函数，按年龄区间由新到旧输出文件的总大小，空的区间也会显示
+fn print_age_histogram(stats: &AgeStats, config: &Arguments) {
+    let Some(histogram) = stats.histogram() else {
+        return;
+    };
+    let time = match stats.time {
+        AgeTime::Modified => "modification",
+        AgeTime::Accessed => "access",
+    };
+    println!("\nUsage by {} time:", time);
+    let total = histogram.iter().map(|(_, group)| group.size).sum::<u64>();
+    for (name, group) in &histogram {
+        let percent = match total {
+            0 => 0.0,
+            _ => 100.0 * group.size as f64 / total as f64,
+        };
+        print_usage_line("", percent, name, group, config);
+    }
+}
+
+/// 函数，汇总报告中的一行
+fn print_usage_line(
+    indent: &str,
+    percent: f64,
+    name: &str,
+    group: &UsageGroup,
+    config: &Arguments,
+) {
+    println!(
+        "{}{:>w$.p$}% [{}] {} {} ({} files)",
+        indent,
+        percent,
+        convert_to_bytes(group.size as f64),
+        tree_shape::SPACING,
+        name,
+        group.count,
+        w = config.decimal_num + 4,
+        p = config.decimal_num
+    );
+}
+
+/// 函数，在树形结构之后输出扫描的统计信息，导入的结果没有扫描过程中的统计
+fn print_summary(
+    analysed: &AnalysisItem,
+    context: Option<&AnalysisContext>,
+    config: &Arguments,
+    elapsed_time: Duration,
+) {
+    let error_count = analysed.error_count();
+    if error_count > 0 {
+        print!("\n{} entries could not be read", error_count);
+        match config.show_errors {
+            true => println!(),
+            false => println!(" (use --show-errors to list them)"),
+        }
+    }
+    if let Some(context) = context {
+        if context.filter.excluded_count() > 0 {
+            println!("\n{} entries excluded", context.filter.excluded_count());
+        }
+        match context.ignore_rules.mode {
+            IgnoreMode::Respect => println!(
+                "\n{} entries ignored by .gitignore rules",
+                context.ignore_rules.skipped_count()
+            ),
+            IgnoreMode::OnlyIgnored => println!(
+                "\n{} entries not ignored by .gitignore rules were skipped",
+                context.ignore_rules.skipped_count()
+            ),
+            IgnoreMode::Off => {}
+        }
+        if context.ages.is_filtering() {
+            println!(
+                "\n{} files outside the age filter were not counted",
+                context.ages.filtered_count()
+            );
+        }
+        if context.hard_links.duplicate_count() > 0 {
+            println!(
+                "\nHard links: {} duplicate entries ({}) counted only once",
+                context.hard_links.duplicate_count(),
+                convert_to_bytes(context.hard_links.duplicate_size() as f64)
+            );
+        }
+        if let Some(cache) = &context.cache {
+            println!(
+                "\n{} of {} directories came from the cache",
+                cache.reused_count(),
+                context.progress.dirs()
+            );
+        }
+    }
+    match context {
+        // 调整 --threads 与 --io-mode 时参考的扫描速度
+        Some(context) => {
+            let entries = context.progress.files() + context.progress.dirs();
+            println!(
+                "\nElapsed time: {:?} ({} entries, {:.0} entries/sec, {} threads)",
+                elapsed_time,
+                entries,
+                entries as f64 / elapsed_time.as_secs_f64(),
+                config.scan_threads()
+            );
+        }
+        None => println!("\nElapsed time: {:?}", elapsed_time),
+    }
 }
@@ -3,9 +3,10 @@ use std::io::Write;
 use termcolor::{Buffer, ColorSpec, WriteColor};
 
 use crate::struct_define::analysis_item::AnalysisItem;
-use crate::struct_define::config::Arguments;
+use crate::struct_define::config::{Arguments, SizeMode};
 use crate::struct_define::display_color::COLOR_GRAY;
 use crate::struct_define::display_info::DisplayItemInfo;
+use crate::struct_define::size_diff::SizeDiff;
 use crate::struct_define::tree_shape;
 
 #[cfg(windows)]
@@ -20,15 +21,11 @@ pub fn show_disk_analyze_result(
     info: &DisplayItemInfo,
     buffer: &mut Buffer,
 ) -> io::Result<()> {
-    show_disk_analyze_item(item, &config, &info, buffer)?;
+    show_disk_analyze_item(item, config, info, buffer)?;
 
     if info.dir_level < config.max_depth {
         if let Some(children) = &item.children {
-            let children = children
-                .iter()
-                .map(|child| (child, size_fraction(child, item)))
-                .filter(|&(_, occupied_size)| occupied_size > config.min_percent)
-                .collect::<Vec<_>>();
+            let children = visible_children(children, item, config);
 
             if let Some((last_child, children)) = children.split_last() {
                 for &(child, occupied_size) in children.iter() {
@@ -52,6 +49,22 @@ pub fn show_disk_analyze_result(
     Ok(())
 }
 
+/// 函数，按 --min-percent 筛选需要显示的子项，并计算其占父目录的百分比
+pub fn visible_children<'a>(
+    children: &'a [AnalysisItem],
+    parent: &AnalysisItem,
+    config: &Arguments,
+) -> Vec<(&'a AnalysisItem, f64)> {
+    children
+        .iter()
+        .map(|child| (child, size_fraction(child, parent, config.size_mode())))
+        .filter(|&(child, occupied_size)| {
+            // 挂载点即使未扫描也始终显示
+            occupied_size > config.min_percent || child.mount.is_some()
+        })
+        .collect()
+}
+
 /// 函数，帮助信息
 pub fn _show_help() -> io::Result<()> {
     Ok(())
@@ -86,18 +99,129 @@ pub fn show_disk_analyze_item(
     )?;
     // Disk size
     buffer.set_color(ColorSpec::new().set_fg(info.display_color(true)))?;
-    write!(buffer, "[{}]", convert_to_bytes(item.disk_size as f64),)?;
+    match config.size_mode() {
+        SizeMode::Apparent => write!(buffer, "[{}]", convert_to_bytes(item.apparent_size as f64))?,
+        SizeMode::Allocated => {
+            write!(buffer, "[{}]", convert_to_bytes(item.allocated_size as f64))?
+        }
+        // 实际占用 | 文件长度
+        SizeMode::Both => write!(
+            buffer,
+            "[{} | {}]",
+            convert_to_bytes(item.allocated_size as f64),
+            convert_to_bytes(item.apparent_size as f64)
+        )?,
+    }
     // Arrow
     buffer.set_color(ColorSpec::new().set_fg(COLOR_GRAY))?;
     write!(buffer, " {} ", tree_shape::SPACING)?;
     // Name
     buffer.reset()?;
-    writeln!(buffer, "{}", item.name)?;
+    write!(buffer, "{}", item.name.to_string_lossy())?;
+    // Symbolic link
+    if let Some(symlink) = &item.symlink {
+        buffer.set_color(ColorSpec::new().set_fg(COLOR_GRAY))?;
+        write!(buffer, " -> {}", symlink.target.display())?;
+        if symlink.cycle {
+            write!(buffer, " (cycle, not followed)")?;
+        }
+        if symlink.duplicate {
+            write!(buffer, " (already counted, not followed)")?;
+        }
+        buffer.reset()?;
+    }
+    // Mount point
+    if let Some(mount) = &item.mount {
+        buffer.set_color(ColorSpec::new().set_fg(COLOR_GRAY))?;
+        match mount.scanned {
+            true => write!(buffer, " [mount: {}]", mount.source)?,
+            false => write!(buffer, " [mount: {}, not scanned]", mount.source)?,
+        }
+        buffer.reset()?;
+    }
+    // 扫描被中断
+    if item.partial {
+        buffer.set_color(ColorSpec::new().set_fg(COLOR_GRAY))?;
+        write!(buffer, " (partial)")?;
+        buffer.reset()?;
+    }
+    writeln!(buffer)?;
+    Ok(())
+}
+
+/// 函数，两次扫描之间的大小变化，只显示有变化的条目，颜色深浅取决于变化量占 total_change 的比例
+pub fn show_diff_result(
+    item: &SizeDiff,
+    config: &Arguments,
+    info: &DisplayItemInfo,
+    total_change: u64,
+    buffer: &mut Buffer,
+) -> io::Result<()> {
+    show_diff_item(item, info, buffer)?;
+
+    if info.dir_level < config.max_depth {
+        let children = item
+            .children
+            .iter()
+            .filter(|child| child.is_changed())
+            .collect::<Vec<_>>();
+        for (index, child) in children.iter().enumerate() {
+            let share = match total_change {
+                0 => 0.0,
+                _ => 100.0 * child.delta().unsigned_abs() as f64 / total_change as f64,
+            };
+            show_diff_result(
+                child,
+                config,
+                &info.add_item(share, index + 1 == children.len()),
+                total_change,
+                buffer,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// 函数，两次扫描之间的大小变化 —— 单个项
+pub fn show_diff_item(
+    item: &SizeDiff,
+    info: &DisplayItemInfo,
+    buffer: &mut Buffer,
+) -> io::Result<()> {
+    // Indentation
+    buffer.set_color(ColorSpec::new().set_fg(COLOR_GRAY))?;
+    write!(buffer, "{}{}", info.prefix, info.display_prefix(true))?;
+    // Delta
+    let delta = item.delta();
+    buffer.set_color(ColorSpec::new().set_fg(info.display_growth_color(delta)))?;
+    let sign = if delta > 0 { "+" } else { "" };
+    write!(buffer, " {}{} ", sign, convert_to_bytes(delta as f64))?;
+    // Old and new size
+    buffer.set_color(ColorSpec::new().set_fg(COLOR_GRAY))?;
+    write!(
+        buffer,
+        "[{} -> {}] {} ",
+        convert_to_bytes(item.old_size as f64),
+        convert_to_bytes(item.new_size as f64),
+        tree_shape::SPACING
+    )?;
+    // Name
+    buffer.reset()?;
+    write!(buffer, "{}", item.name.to_string_lossy())?;
+    // Change
+    buffer.set_color(ColorSpec::new().set_fg(info.display_growth_color(delta)))?;
+    write!(buffer, " ({})", item.change.label())?;
+    buffer.reset()?;
+    writeln!(buffer)?;
     Ok(())
 }
 
-pub fn size_fraction(child: &AnalysisItem, parent: &AnalysisItem) -> f64 {
-    100.0 * (child.disk_size as f64 / parent.disk_size as f64)
+pub fn size_fraction(child: &AnalysisItem, parent: &AnalysisItem, size_mode: SizeMode) -> f64 {
+    match parent.size(size_mode) {
+        // 空目录（如未扫描的挂载点）避免出现 NaN%
+        0 => 0.0,
+        parent_size => 100.0 * (child.size(size_mode) as f64 / parent_size as f64),
+    }
 }
 
 // pretty_bytes::converter::convert 据此修改修改
@@ -122,6 +246,32 @@ pub fn convert_to_bytes(num: f64) -> String {
     format!("{}{} {}", negative, pretty_bytes, unit)
 }
 
+/// 函数，将 Unix 秒格式化为本地时间 YYYY-MM-DDThh:mm:ss
+#[cfg(unix)]
+pub fn format_local_time(secs: u64) -> String {
+    let time = secs as libc::time_t;
+    // SAFETY: localtime_r 只写入传入的 tm
+    let tm = unsafe {
+        let mut tm = std::mem::zeroed::<libc::tm>();
+        libc::localtime_r(&time, &mut tm);
+        tm
+    };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec
+    )
+}
+
+#[cfg(not(unix))]
+pub fn format_local_time(secs: u64) -> String {
+    format!("@{}", secs)
+}
+
 #[cfg(windows)]
 pub fn compressed_size(path: &Path) -> Result<u64, Box<dyn Error>> {
     use std::iter::once;
@@ -0,0 +1,180 @@
+//! `--output csv` 与 `--output tsv`，每个条目一行：
+//!
+//! ```txt
+//! path,depth,kind,size,percent_of_parent,percent_of_root,child_count
+//! ```
+//!
+//! `size` 为 `--size-mode` 对应的大小（both 时为实际占用），单位为字节。
+//! 路径按原始字节输出，不做 UTF-8 转换。CSV 按 RFC 4180 在需要时加引号，
+//! TSV 将字段中的 `\`、制表符与换行转义为 `\\`、`\t`、`\n`、`\r`。
+
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::methods::{size_fraction, visible_children};
+use crate::struct_define::analysis_item::AnalysisItem;
+use crate::struct_define::config::Arguments;
+
+/// 枚举，按分隔符区分的表格格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimited {
+    Csv,
+    Tsv,
+}
+
+impl Delimited {
+    fn separator(self) -> &'static str {
+        match self {
+            Delimited::Csv => ",",
+            Delimited::Tsv => "\t",
+        }
+    }
+
+    fn escape(self, field: &[u8]) -> Cow<'_, [u8]> {
+        match self {
+            Delimited::Csv => escape_csv(field),
+            Delimited::Tsv => escape_tsv(field),
+        }
+    }
+}
+
+const HEADER: [&str; 7] = [
+    "path",
+    "depth",
+    "kind",
+    "size",
+    "percent_of_parent",
+    "percent_of_root",
+    "child_count",
+];
+
+/// 函数，将分析结果展开为每个条目一行的表格
+pub fn write_delimited<W: Write>(
+    item: &AnalysisItem,
+    root: &Path,
+    config: &Arguments,
+    format: Delimited,
+    writer: &mut W,
+) -> io::Result<()> {
+    writeln!(writer, "{}", HEADER.join(format.separator()))?;
+    let rows = DelimitedRows {
+        root: item,
+        config,
+        format,
+    };
+    rows.write_item(item, root, 0, 100.0, writer)
+}
+
+struct DelimitedRows<'a> {
+    root: &'a AnalysisItem,
+    config: &'a Arguments,
+    format: Delimited,
+}
+
+impl DelimitedRows<'_> {
+    fn write_item<W: Write>(
+        &self,
+        item: &AnalysisItem,
+        path: &Path,
+        depth: usize,
+        percent_of_parent: f64,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let size_mode = self.config.size_mode();
+        let kind = match (&item.children, &item.symlink) {
+            (Some(_), _) => "dir",
+            (None, Some(_)) => "symlink",
+            (None, None) => "file",
+        };
+        writer.write_all(&self.format.escape(&path_bytes(path)))?;
+        // 其余字段不含分隔符，无需转义
+        let separator = self.format.separator();
+        writeln!(
+            writer,
+            "{sep}{}{sep}{}{sep}{}{sep}{:.prec$}{sep}{:.prec$}{sep}{}",
+            depth,
+            kind,
+            item.size(size_mode),
+            percent_of_parent,
+            size_fraction(item, self.root, size_mode),
+            item.children.as_ref().map_or(0, Vec::len),
+            sep = separator,
+            prec = self.config.decimal_num,
+        )?;
+
+        if let Some(children) = &item.children {
+            if self.config.full || depth < self.config.max_depth {
+                let children = match self.config.full {
+                    true => children
+                        .iter()
+                        .map(|child| (child, size_fraction(child, item, size_mode)))
+                        .collect(),
+                    false => visible_children(children, item, self.config),
+                };
+                for (child, percent) in children {
+                    let child_path = path.join(&child.name);
+                    self.write_item(child, &child_path, depth + 1, percent, writer)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 函数，路径的原始字节
+#[cfg(unix)]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(path.as_os_str().as_bytes())
+}
+
+/// 函数，路径的原始字节，Windows 下无法表示为 UTF-8 的部分会被替换
+#[cfg(not(unix))]
+fn path_bytes(path: &Path) -> Cow<'_, [u8]> {
+    match path.to_string_lossy() {
+        Cow::Borrowed(path) => Cow::Borrowed(path.as_bytes()),
+        Cow::Owned(path) => Cow::Owned(path.into_bytes()),
+    }
+}
+
+/// 函数，RFC 4180：含有逗号、引号或换行的字段加引号，并将引号写两次
+fn escape_csv(field: &[u8]) -> Cow<'_, [u8]> {
+    if !field
+        .iter()
+        .any(|byte| matches!(byte, b',' | b'"' | b'\n' | b'\r'))
+    {
+        return Cow::Borrowed(field);
+    }
+    let mut escaped = Vec::with_capacity(field.len() + 2);
+    escaped.push(b'"');
+    for &byte in field {
+        if byte == b'"' {
+            escaped.push(b'"');
+        }
+        escaped.push(byte);
+    }
+    escaped.push(b'"');
+    Cow::Owned(escaped)
+}
+
+/// 函数，TSV 字段不能包含制表符与换行，以反斜杠转义
+fn escape_tsv(field: &[u8]) -> Cow<'_, [u8]> {
+    if !field
+        .iter()
+        .any(|byte| matches!(byte, b'\\' | b'\t' | b'\n' | b'\r'))
+    {
+        return Cow::Borrowed(field);
+    }
+    let mut escaped = Vec::with_capacity(field.len() + 2);
+    for &byte in field {
+        match byte {
+            b'\\' => escaped.extend_from_slice(b"\\\\"),
+            b'\t' => escaped.extend_from_slice(b"\\t"),
+            b'\n' => escaped.extend_from_slice(b"\\n"),
+            b'\r' => escaped.extend_from_slice(b"\\r"),
+            _ => escaped.push(byte),
+        }
+    }
+    Cow::Owned(escaped)
+}
@@ -0,0 +1,78 @@
+//! `mrdu dupes --output json` 的输出格式：
+//!
+//! ```txt
+//! {
+//!   "version": 1,
+//!   "root": "tests/test_file",        // 扫描的目录，与命令行参数一致
+//!   "wasted": 12288,                  // 所有重复文件只保留一份时可释放的字节数
+//!   "sets": [                         // 按可释放的字节数降序
+//!     { "size": 4096, "wasted": 8192, "paths": ["tests/test_file/a", ...] }
+//!   ]
+//! }
+//! ```
+
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::io::Write;
+use std::path::Path;
+
+use crate::struct_define::duplicates::DuplicateSet;
+
+pub const DUPES_SCHEMA_VERSION: u32 = 1;
+
+/// 函数，以 JSON 格式输出重复文件
+pub fn write_dupes_json<W: Write>(
+    sets: &[DuplicateSet],
+    root: &Path,
+    writer: &mut W,
+) -> serde_json::Result<()> {
+    serde_json::to_writer(&mut *writer, &DupesReport { sets, root })?;
+    writeln!(writer).map_err(serde_json::Error::io)
+}
+
+struct DupesReport<'a> {
+    sets: &'a [DuplicateSet],
+    root: &'a Path,
+}
+
+impl Serialize for DupesReport<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let wasted = self.sets.iter().map(DuplicateSet::wasted).sum::<u64>();
+        let mut map = serializer.serialize_map(Some(4))?;
+        map.serialize_entry("version", &DUPES_SCHEMA_VERSION)?;
+        map.serialize_entry("root", &self.root.to_string_lossy())?;
+        map.serialize_entry("wasted", &wasted)?;
+        map.serialize_entry("sets", &DupesSets(self.sets))?;
+        map.end()
+    }
+}
+
+struct DupesSets<'a>(&'a [DuplicateSet]);
+
+impl Serialize for DupesSets<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for set in self.0 {
+            seq.serialize_element(&DupesSet(set))?;
+        }
+        seq.end()
+    }
+}
+
+struct DupesSet<'a>(&'a DuplicateSet);
+
+impl Serialize for DupesSet<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let paths = self
+            .0
+            .paths
+            .iter()
+            .map(|path| path.to_string_lossy())
+            .collect::<Vec<_>>();
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("size", &self.0.size)?;
+        map.serialize_entry("wasted", &self.0.wasted())?;
+        map.serialize_entry("paths", &paths)?;
+        map.end()
+    }
+}
@@ -0,0 +1,176 @@
+//! `--output json` 的输出格式，字段只增不改，新增字段时 `version` 保持不变。
+//!
+//! ```txt
+//! {
+//!   "version": 1,
+//!   "root": "tests/test_file",        // 扫描的目录，与命令行参数一致
+//!   "size_mode": "allocated",         // apparent | allocated | both
+//!   "tree": <item>,
+//!   "errors": [                       // 无法读取而被跳过的条目
+//!     { "path": "...", "kind": "PermissionDenied", "message": "..." }
+//!   ]
+//! }
+//!
+//! <item> = {
+//!   "name": "test_file",
+//!   "path": "tests/test_file",        // 以 root 开头的完整路径
+//!   "kind": "dir",                    // dir | file | symlink
+//!   "apparent_size": 26232,           // 字节
+//!   "allocated_size": 61440,          // 字节
+//!   "file_count": 10,                 // 子树中的文件数，不含自身
+//!   "dir_count": 5,                   // 子树中的目录数，不含自身
+//!   "symlink_target": "../real",      // 仅符号链接
+//!   "mount": { "source": "/dev/sdb1", "scanned": false },  // 仅挂载点
+//!   "partial": true,                  // 仅扫描被中断、未完整读取的目录
+//!   "children": [<item>, ...]         // 仅目录，超出 --max-depth 时省略
+//! }
+//! ```
+//!
+//! 未指定 `--full` 时，`children` 遵循 `--max-depth` 与 `--min-percent`，
+//! 但 `file_count`、`dir_count` 与大小总是统计整棵子树。
+
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::methods::visible_children;
+use crate::struct_define::analysis_item::AnalysisItem;
+use crate::struct_define::config::{Arguments, SizeMode};
+use crate::struct_define::scan_error::ScanError;
+
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// 函数，以 JSON 格式输出分析结果
+pub fn write_json<W: Write>(
+    item: &AnalysisItem,
+    root: &Path,
+    config: &Arguments,
+    writer: &mut W,
+) -> serde_json::Result<()> {
+    let report = JsonReport { item, root, config };
+    serde_json::to_writer(&mut *writer, &report)?;
+    writeln!(writer).map_err(serde_json::Error::io)
+}
+
+struct JsonReport<'a> {
+    item: &'a AnalysisItem,
+    root: &'a Path,
+    config: &'a Arguments,
+}
+
+impl Serialize for JsonReport<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let size_mode = match self.config.size_mode() {
+            SizeMode::Apparent => "apparent",
+            SizeMode::Allocated => "allocated",
+            SizeMode::Both => "both",
+        };
+        let tree = JsonItem {
+            item: self.item,
+            path: self.root.to_path_buf(),
+            depth: 0,
+            config: self.config,
+        };
+        let mut map = serializer.serialize_map(Some(5))?;
+        map.serialize_entry("version", &JSON_SCHEMA_VERSION)?;
+        map.serialize_entry("root", &self.root.to_string_lossy())?;
+        map.serialize_entry("size_mode", size_mode)?;
+        map.serialize_entry("tree", &tree)?;
+        map.serialize_entry("errors", &JsonErrors(self.item.collect_errors()))?;
+        map.end()
+    }
+}
+
+struct JsonItem<'a> {
+    item: &'a AnalysisItem,
+    path: PathBuf,
+    depth: usize,
+    config: &'a Arguments,
+}
+
+impl Serialize for JsonItem<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let item = self.item;
+        let kind = match (&item.children, &item.symlink) {
+            (Some(_), _) => "dir",
+            (None, Some(_)) => "symlink",
+            (None, None) => "file",
+        };
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("name", &item.name.to_string_lossy())?;
+        map.serialize_entry("path", &self.path.to_string_lossy())?;
+        map.serialize_entry("kind", kind)?;
+        map.serialize_entry("apparent_size", &item.apparent_size)?;
+        map.serialize_entry("allocated_size", &item.allocated_size)?;
+        map.serialize_entry("file_count", &item.file_count)?;
+        map.serialize_entry("dir_count", &item.dir_count)?;
+        if let Some(symlink) = &item.symlink {
+            map.serialize_entry("symlink_target", &symlink.target.to_string_lossy())?;
+        }
+        if let Some(mount) = &item.mount {
+            map.serialize_entry(
+                "mount",
+                &serde_json::json!({ "source": mount.source, "scanned": mount.scanned }),
+            )?;
+        }
+        if item.partial {
+            map.serialize_entry("partial", &true)?;
+        }
+        if let Some(children) = &item.children {
+            if self.config.full || self.depth < self.config.max_depth {
+                let children = match self.config.full {
+                    true => children.iter().collect(),
+                    false => visible_children(children, item, self.config)
+                        .into_iter()
+                        .map(|(child, _)| child)
+                        .collect::<Vec<_>>(),
+                };
+                map.serialize_entry(
+                    "children",
+                    &JsonChildren {
+                        parent: self,
+                        children,
+                    },
+                )?;
+            }
+        }
+        map.end()
+    }
+}
+
+struct JsonChildren<'a> {
+    parent: &'a JsonItem<'a>,
+    children: Vec<&'a AnalysisItem>,
+}
+
+impl Serialize for JsonChildren<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.children.len()))?;
+        for child in &self.children {
+            seq.serialize_element(&JsonItem {
+                item: child,
+                path: self.parent.path.join(&child.name),
+                depth: self.parent.depth + 1,
+                config: self.parent.config,
+            })?;
+        }
+        seq.end()
+    }
+}
+
+struct JsonErrors<'a>(Vec<&'a ScanError>);
+
+impl Serialize for JsonErrors<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for error in &self.0 {
+            seq.serialize_element(&serde_json::json!({
+                "path": error.path.to_string_lossy(),
+                "kind": format!("{:?}", error.kind),
+                "message": error.message,
+            }))?;
+        }
+        seq.end()
+    }
+}
@@ -0,0 +1,19 @@
+pub mod delimited;
+pub mod dupes;
+pub mod json;
+pub mod ncdu;
+pub mod snapshot;
+
+use serde::Deserialize;
+use serde_json::Value;
+use std::io::{self, Read};
+
+/// 函数，读取 mrdu 写出的 JSON 文件。目录树有多深，JSON 就嵌套多深，
+/// 因此不使用 serde_json 默认的 128 层嵌套限制
+pub(crate) fn read_json_value<R: Read>(reader: R) -> serde_json::Result<Value> {
+    let mut deserializer = serde_json::Deserializer::from_reader(io::BufReader::new(reader));
+    deserializer.disable_recursion_limit();
+    let value = Value::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
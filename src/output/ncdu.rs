@@ -0,0 +1,212 @@
+//! ncdu 的 JSON 导出格式（major 1, minor 2），可与 `ncdu -o` / `ncdu -f` 互通：
+//!
+//! ```txt
+//! [1, 2, {"progname": "mrdu", "progver": "0.1.0", "timestamp": 1700000000},
+//!   [{"name": "/abs/path/of/root"},
+//!     {"name": "file", "asize": 5139, "dsize": 8192},
+//!     [{"name": "dir", "read_error": true},
+//!       {"name": "link", "asize": 7, "dsize": 0, "notreg": true}],
+//!     {"name": "mnt", "excluded": "otherfs"}]]
+//! ```
+//!
+//! 目录是以自身信息开头的数组，文件是对象。mrdu 不统计目录本身占用的空间，
+//! 导出时目录不带 `asize`/`dsize`，导入时也忽略目录的这两个字段。
+//! 导入时 `notreg` 条目按普通文件处理，因为 ncdu 不记录符号链接的指向。
+//! 带 `hlnkc` 的文件按 (`dev`, `ino`) 去重，与扫描时一样只计数一次，`dev` 省略时继承自上级目录。
+
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::error::Error;
+use std::ffi::OsString;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::output::read_json_value;
+use crate::struct_define::analysis_item::AnalysisItem;
+use crate::struct_define::config::SizeMode;
+use crate::struct_define::hard_link::HardLinkTracker;
+use crate::struct_define::mount_point::MountPoint;
+use crate::struct_define::scan_error::ScanError;
+
+const NCDU_MAJOR_VERSION: u64 = 1;
+const NCDU_MINOR_VERSION: u64 = 2;
+
+/// 函数，以 ncdu 导出格式写出整棵树，root 为扫描的目录
+pub fn write_ncdu<W: Write>(item: &AnalysisItem, root: &Path, writer: &mut W) -> io::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    // ncdu 以绝对路径作为根目录的名称
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let header = serde_json::json!({
+        "progname": env!("CARGO_PKG_NAME"),
+        "progver": env!("CARGO_PKG_VERSION"),
+        "timestamp": timestamp,
+    });
+    let tree = NcduItem {
+        item,
+        name: root.to_string_lossy().into_owned(),
+    };
+    serde_json::to_writer(
+        &mut *writer,
+        &(NCDU_MAJOR_VERSION, NCDU_MINOR_VERSION, header, tree),
+    )?;
+    writeln!(writer)
+}
+
+struct NcduItem<'a> {
+    item: &'a AnalysisItem,
+    name: String,
+}
+
+impl Serialize for NcduItem<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let item = self.item;
+        match &item.children {
+            // 未扫描的挂载点
+            Some(_) if item.mount.as_ref().is_some_and(|mount| !mount.scanned) => {
+                let mut map = serializer.serialize_map(Some(2))?;
+                map.serialize_entry("name", &self.name)?;
+                map.serialize_entry("excluded", "otherfs")?;
+                map.end()
+            }
+            Some(children) => {
+                let mut seq = serializer.serialize_seq(Some(children.len() + 1))?;
+                let mut info = serde_json::Map::new();
+                info.insert("name".to_string(), Value::from(self.name.as_str()));
+                if !item.errors.is_empty() {
+                    info.insert("read_error".to_string(), Value::from(true));
+                }
+                seq.serialize_element(&info)?;
+                for child in children {
+                    seq.serialize_element(&NcduItem {
+                        item: child,
+                        name: child.name.to_string_lossy().into_owned(),
+                    })?;
+                }
+                seq.end()
+            }
+            None => {
+                let mut map = serializer.serialize_map(None)?;
+                map.serialize_entry("name", &self.name)?;
+                map.serialize_entry("asize", &item.apparent_size)?;
+                map.serialize_entry("dsize", &item.allocated_size)?;
+                if item.symlink.is_some() {
+                    map.serialize_entry("notreg", &true)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+/// 函数，读取 ncdu 导出的文件，返回根目录的路径与分析结果
+pub fn read_ncdu<R: Read>(
+    reader: R,
+    size_mode: SizeMode,
+) -> Result<(PathBuf, AnalysisItem), Box<dyn Error>> {
+    let value = read_json_value(reader)?;
+    let parts = value
+        .as_array()
+        .ok_or("not an ncdu export: expected an array")?;
+    if parts.first().and_then(Value::as_u64) != Some(NCDU_MAJOR_VERSION) || parts.len() < 4 {
+        return Err("not an ncdu export: unsupported format version".into());
+    }
+    let root = parts[3]
+        .as_array()
+        .and_then(|dir| dir.first())
+        .and_then(|info| info["name"].as_str())
+        .ok_or("not an ncdu export: missing root directory")?;
+    let root = PathBuf::from(root);
+    let mut item = read_ncdu_entry(&parts[3], &root, 0, &HardLinkTracker::new(), size_mode)?;
+    // 与扫描时一致，根目录以最后一级目录名显示
+    if let Some(name) = root.file_name() {
+        item.name = name.to_os_string();
+    }
+    Ok((root, item))
+}
+
+/// dev 为上级目录的设备号，hard_links 记录已导入的硬链接
+fn read_ncdu_entry(
+    value: &Value,
+    path: &Path,
+    dev: u64,
+    hard_links: &HardLinkTracker,
+    size_mode: SizeMode,
+) -> Result<AnalysisItem, Box<dyn Error>> {
+    let (info, entries) = match value {
+        Value::Array(dir) => {
+            let (info, entries) = dir.split_first().ok_or("ncdu export: empty directory")?;
+            (info, Some(entries))
+        }
+        info => (info, None),
+    };
+    let name = info["name"]
+        .as_str()
+        .ok_or("ncdu export: entry without name")?;
+    let mut item = AnalysisItem::new(OsString::from(name));
+    let dev = info["dev"].as_u64().unwrap_or(dev);
+
+    match entries {
+        Some(entries) => {
+            let mut children = Vec::with_capacity(entries.len());
+            for entry in entries {
+                // 被 ncdu 排除的条目没有大小，与 --exclude 一样不显示
+                let excluded = entry.get("excluded").and_then(Value::as_str);
+                if excluded.is_some() && excluded != Some("otherfs") {
+                    continue;
+                }
+                let child_name = entry_name(entry).unwrap_or_default();
+                children.push(read_ncdu_entry(
+                    entry,
+                    &path.join(child_name),
+                    dev,
+                    hard_links,
+                    size_mode,
+                )?);
+            }
+            item.set_children(children, size_mode);
+            if info["read_error"].as_bool() == Some(true) {
+                item.errors.push(ScanError {
+                    path: path.to_path_buf(),
+                    kind: io::ErrorKind::Other,
+                    message: "read error reported by ncdu export".to_string(),
+                });
+            }
+        }
+        None if info["excluded"].as_str() == Some("otherfs") => {
+            item.children = Some(Vec::new());
+            item.mount = Some(MountPoint {
+                source: "other filesystem".to_string(),
+                scanned: false,
+            });
+        }
+        None => {
+            let apparent_size = info["asize"].as_u64().unwrap_or(0);
+            let allocated_size = info["dsize"].as_u64().unwrap_or(0);
+            // 同一 inode 的多个硬链接只计数一次，ncdu 省略 nlink 时视为多于一个链接
+            let duplicate = match (info["hlnkc"].as_bool(), info["ino"].as_u64()) {
+                (Some(true), Some(inode)) => {
+                    let nlink = info["nlink"].as_u64().unwrap_or(2);
+                    let size = size_mode.primary(apparent_size, allocated_size);
+                    hard_links.is_duplicate(dev, inode, nlink, size)
+                }
+                _ => false,
+            };
+            if !duplicate {
+                item.apparent_size = apparent_size;
+                item.allocated_size = allocated_size;
+            }
+        }
+    }
+    Ok(item)
+}
+
+fn entry_name(entry: &Value) -> Option<&str> {
+    match entry {
+        Value::Array(dir) => dir.first()?["name"].as_str(),
+        info => info["name"].as_str(),
+    }
+}
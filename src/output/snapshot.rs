@@ -0,0 +1,171 @@
+//! `mrdu snapshot save` 保存的快照格式，记录整棵树而不受 `--max-depth` 与 `--min-percent` 影响：
+//!
+//! ```txt
+//! {
+//!   "version": 1,
+//!   "timestamp": 1700000000,          // 保存时间，Unix 秒
+//!   "root": "/abs/path/of/root",      // 扫描的目录的绝对路径
+//!   "tree": <item>
+//! }
+//!
+//! <item> = {
+//!   "name": "root",
+//!   "apparent_size": 26232,           // 仅文件与符号链接，目录的大小由子项汇总
+//!   "allocated_size": 61440,
+//!   "symlink_target": "../real",      // 仅符号链接
+//!   "mount": { "source": "/dev/sdb1", "scanned": false },  // 仅挂载点
+//!   "children": [<item>, ...]         // 仅目录
+//! }
+//! ```
+
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::error::Error;
+use std::ffi::OsString;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::output::read_json_value;
+use crate::struct_define::analysis_item::AnalysisItem;
+use crate::struct_define::config::SizeMode;
+use crate::struct_define::mount_point::MountPoint;
+use crate::struct_define::symbolic_link::SymbolicLink;
+
+pub const SNAPSHOT_VERSION: u64 = 1;
+
+/// 结构体，读取的快照
+pub struct Snapshot {
+    pub root: PathBuf,
+    /// 保存时间，Unix 秒
+    pub timestamp: u64,
+    pub tree: AnalysisItem,
+}
+
+/// 函数，保存整棵树及当前时间，root 为扫描的目录
+pub fn write_snapshot<W: Write>(
+    item: &AnalysisItem,
+    root: &Path,
+    writer: &mut W,
+) -> serde_json::Result<()> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    // 与实时扫描对比时需要再次找到该目录
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let report = SnapshotReport {
+        item,
+        root: &root,
+        timestamp,
+    };
+    serde_json::to_writer(&mut *writer, &report)?;
+    writeln!(writer).map_err(serde_json::Error::io)
+}
+
+struct SnapshotReport<'a> {
+    item: &'a AnalysisItem,
+    root: &'a Path,
+    timestamp: u64,
+}
+
+impl Serialize for SnapshotReport<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(4))?;
+        map.serialize_entry("version", &SNAPSHOT_VERSION)?;
+        map.serialize_entry("timestamp", &self.timestamp)?;
+        map.serialize_entry("root", &self.root.to_string_lossy())?;
+        map.serialize_entry("tree", &SnapshotItem(self.item))?;
+        map.end()
+    }
+}
+
+struct SnapshotItem<'a>(&'a AnalysisItem);
+
+impl Serialize for SnapshotItem<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let item = self.0;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("name", &item.name.to_string_lossy())?;
+        if !item.is_dir() {
+            map.serialize_entry("apparent_size", &item.apparent_size)?;
+            map.serialize_entry("allocated_size", &item.allocated_size)?;
+        }
+        if let Some(symlink) = &item.symlink {
+            map.serialize_entry("symlink_target", &symlink.target.to_string_lossy())?;
+        }
+        if let Some(mount) = &item.mount {
+            map.serialize_entry(
+                "mount",
+                &serde_json::json!({ "source": mount.source, "scanned": mount.scanned }),
+            )?;
+        }
+        if let Some(children) = &item.children {
+            map.serialize_entry("children", &SnapshotChildren(children))?;
+        }
+        map.end()
+    }
+}
+
+struct SnapshotChildren<'a>(&'a [AnalysisItem]);
+
+impl Serialize for SnapshotChildren<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut seq = serializer.serialize_seq(Some(self.0.len()))?;
+        for child in self.0 {
+            seq.serialize_element(&SnapshotItem(child))?;
+        }
+        seq.end()
+    }
+}
+
+/// 函数，读取快照，size_mode 决定子项的排列顺序
+pub fn read_snapshot<R: Read>(reader: R, size_mode: SizeMode) -> Result<Snapshot, Box<dyn Error>> {
+    let value = read_json_value(reader)?;
+    if value["version"].as_u64() != Some(SNAPSHOT_VERSION) {
+        return Err("not an mrdu snapshot: unsupported version".into());
+    }
+    let root = value["root"]
+        .as_str()
+        .ok_or("not an mrdu snapshot: missing root")?;
+    Ok(Snapshot {
+        root: PathBuf::from(root),
+        timestamp: value["timestamp"].as_u64().unwrap_or(0),
+        tree: read_snapshot_item(&value["tree"], size_mode)?,
+    })
+}
+
+fn read_snapshot_item(value: &Value, size_mode: SizeMode) -> Result<AnalysisItem, Box<dyn Error>> {
+    let name = value["name"]
+        .as_str()
+        .ok_or("mrdu snapshot: entry without name")?;
+    let mut item = AnalysisItem::new(OsString::from(name));
+    if let Some(target) = value["symlink_target"].as_str() {
+        item.symlink = Some(SymbolicLink {
+            target: PathBuf::from(target),
+            followed: value.get("children").is_some(),
+            cycle: false,
+            duplicate: false,
+        });
+    }
+    if let Some(mount) = value.get("mount") {
+        item.mount = Some(MountPoint {
+            source: mount["source"].as_str().unwrap_or_default().to_string(),
+            scanned: mount["scanned"].as_bool().unwrap_or(false),
+        });
+    }
+    match value["children"].as_array() {
+        Some(entries) => {
+            let children = entries
+                .iter()
+                .map(|entry| read_snapshot_item(entry, size_mode))
+                .collect::<Result<Vec<_>, _>>()?;
+            item.set_children(children, size_mode);
+        }
+        None => {
+            item.apparent_size = value["apparent_size"].as_u64().unwrap_or(0);
+            item.allocated_size = value["allocated_size"].as_u64().unwrap_or(0);
+        }
+    }
+    Ok(item)
+}
@@ -0,0 +1,90 @@
+use std::path::{Path, PathBuf};
+
+use crate::struct_define::compact_tree::CompactBuilder;
+use crate::struct_define::config::{Arguments, IoMode, SizeMode, SymlinkPolicy};
+use crate::struct_define::entry_filter::EntryFilter;
+use crate::struct_define::file_age::AgeStats;
+use crate::struct_define::file_types::FileTypeStats;
+use crate::struct_define::hard_link::HardLinkTracker;
+use crate::struct_define::ignore_rules::IgnoreRules;
+use crate::struct_define::interrupt::Interrupt;
+use crate::struct_define::owners::{OwnerKind, OwnerStats};
+use crate::struct_define::progress::ScanProgress;
+use crate::struct_define::scan_cache::ScanCache;
+use crate::struct_define::top_entries::TopEntries;
+use crate::struct_define::visited_dirs::VisitedDirs;
+
+/// 结构体，一次扫描过程中所有并行任务共享的参数与状态
+#[derive(Debug)]
+pub struct AnalysisContext {
+    pub size_mode: SizeMode,
+    pub root: PathBuf,
+    pub root_dev: u64,
+    /// 是否进入挂载在扫描目录下的其他文件系统
+    pub cross_filesystems: bool,
+    pub symlinks: SymlinkPolicy,
+    pub io_mode: IoMode,
+    /// 在 Linux 上通过目录的文件描述符读取
+    pub fast_walk: bool,
+    pub hard_links: HardLinkTracker,
+    pub visited_dirs: VisitedDirs,
+    pub filter: EntryFilter,
+    pub ignore_rules: IgnoreRules,
+    /// 最大的文件，以及直接包含的文件最大的目录
+    pub top_files: TopEntries,
+    pub top_dirs: TopEntries,
+    /// 按扩展名或内容类型汇总的文件
+    pub file_types: FileTypeStats,
+    /// 按所有者与用户组汇总的文件
+    pub owners: OwnerStats,
+    pub groups: OwnerStats,
+    /// 按年龄筛选文件并统计年龄分布
+    pub ages: AgeStats,
+    /// 已访问的条目数，用于显示扫描进度
+    pub progress: ScanProgress,
+    /// 被设置后不再进入新的条目
+    pub interrupt: Interrupt,
+    /// 使用 --compact 时，读完的目录被移入其中
+    pub compact: Option<CompactBuilder>,
+    /// 使用 --cache 时复用未变化的目录内容
+    pub cache: Option<ScanCache>,
+}
+
+impl AnalysisContext {
+    pub fn new(config: &Arguments, root: &Path, root_dev: u64) -> Result<Self, globset::Error> {
+        Ok(Self {
+            size_mode: config.size_mode(),
+            root: root.to_path_buf(),
+            root_dev,
+            cross_filesystems: config.cross_filesystems,
+            symlinks: config.symlink_policy(),
+            io_mode: config.io_mode,
+            fast_walk: !config.portable_walk,
+            hard_links: HardLinkTracker::new(),
+            visited_dirs: VisitedDirs::new(),
+            filter: EntryFilter::new(&config.exclude, &config.include)?,
+            ignore_rules: IgnoreRules::new(config.ignore_mode(), root),
+            top_files: TopEntries::new(config.top_files.unwrap_or(0)),
+            top_dirs: TopEntries::new(config.top_dirs.unwrap_or(0)),
+            file_types: FileTypeStats::new(config.file_type_mode()),
+            owners: OwnerStats::new(OwnerKind::User, config.by_owner, config.per_directory),
+            groups: OwnerStats::new(OwnerKind::Group, config.by_group, config.per_directory),
+            ages: AgeStats::new(
+                config.age_time,
+                config.older_than,
+                config.newer_than,
+                config.age_histogram,
+            ),
+            progress: ScanProgress::new(),
+            interrupt: Interrupt::new(),
+            compact: config.compact.then(|| {
+                let fold_percent = config.fold_small_files.then_some(config.min_percent);
+                CompactBuilder::new(config.size_mode(), fold_percent)
+            }),
+            cache: config
+                .cache
+                .as_ref()
+                .map(|file| ScanCache::load(file, config.verify)),
+        })
+    }
+}
@@ -1,57 +1,607 @@
-use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
+use ignore::gitignore::Gitignore;
+use rayon::prelude::{IntoParallelRefIterator, IntoParallelRefMutIterator, ParallelIterator};
+use std::cmp::Reverse;
 use std::error::Error;
-use std::ffi::OsStr;
-use std::fs;
+use std::ffi::{OsStr, OsString};
+use std::io;
 use std::path::Path;
+use std::time::SystemTime;
 
+use crate::struct_define::analysis_context::AnalysisContext;
+use crate::struct_define::compact_tree::ChildRange;
+use crate::struct_define::config::{IoMode, SizeMode, SymlinkPolicy};
+use crate::struct_define::dir_handle::{ChildEntry, DirHandle, EntryKind};
 use crate::struct_define::file_info::FileInfo;
+use crate::struct_define::ignore_rules::{load_dir_rules, IgnoreMode};
+use crate::struct_define::mount_point::MountPoint;
+use crate::struct_define::scan_error::ScanError;
+use crate::struct_define::symbolic_link::SymbolicLink;
 
 pub struct AnalysisItem {
-    pub name: String,
-    pub disk_size: u64,
+    /// 保留原始文件名，非 UTF-8 的字节在显示时才做转换
+    pub name: OsString,
+    pub apparent_size: u64,
+    pub allocated_size: u64,
     pub children: Option<Vec<AnalysisItem>>,
+    /// 该目录下（递归）的文件数与目录数，不含自身
+    pub file_count: u64,
+    pub dir_count: u64,
+    /// 该目录下无法读取而被跳过的条目
+    pub errors: Vec<ScanError>,
+    /// 该目录为挂载点时记录其设备
+    pub mount: Option<MountPoint>,
+    /// 该条目为符号链接时记录其指向
+    pub symlink: Option<SymbolicLink>,
+    /// 扫描被中断时该目录中有未读取的条目
+    pub partial: bool,
+    /// 该条目被 --older-than 等排除，或该目录中有被 --exclude、忽略规则等跳过的条目，均未计入大小
+    pub filtered: bool,
+    /// 使用 --compact 时子条目已移入 CompactTree，记录其位置
+    pub(crate) compacted: Option<ChildRange>,
+}
+
+/// 结构体，当前目录的祖先链，用于识别挂载点与符号链接造成的循环，并逐层应用忽略规则
+struct Ancestor<'a> {
+    volume_id: u64,
+    inode: u64,
+    /// 该目录下的 .gitignore 等规则
+    rules: Option<Gitignore>,
+    /// 该目录本身是否被忽略
+    ignored: bool,
+    /// 用于读取子条目
+    dir: DirHandle,
+    parent: Option<&'a Ancestor<'a>>,
+}
+
+impl<'a> Ancestor<'a> {
+    fn chain(&'a self) -> impl Iterator<Item = &'a Ancestor<'a>> {
+        std::iter::successors(Some(self), |dir| dir.parent)
+    }
+
+    /// 由近及远的忽略规则
+    fn rules(&'a self) -> impl Iterator<Item = &'a Gitignore> {
+        self.chain().filter_map(|dir| dir.rules.as_ref())
+    }
+
+    fn contains(&self, volume_id: u64, inode: u64) -> bool {
+        self.chain()
+            .any(|dir| dir.volume_id == volume_id && dir.inode == inode)
+    }
+
+    /// 打开 item 对应的目录并读取其中的忽略规则，放在堆上以免在递归时占用栈
+    #[inline(never)]
+    fn open(
+        path: &Path,
+        context: &AnalysisContext,
+        parent: Option<&'a Ancestor<'a>>,
+        ignored: bool,
+        item: &AnalysisItem,
+        id: DirId,
+    ) -> io::Result<Box<Self>> {
+        let dir = match (parent, path.file_name()) {
+            (Some(parent), Some(name)) => parent.dir.open_child(name, item.symlink.is_some())?,
+            _ => DirHandle::open(path, context.fast_walk)?,
+        };
+        // 被忽略的目录内部无需再读取规则
+        let rules = match context.ignore_rules.mode {
+            IgnoreMode::Off => None,
+            _ if ignored => None,
+            _ => load_dir_rules(path),
+        };
+        Ok(Box::new(Ancestor {
+            volume_id: id.volume_id,
+            inode: id.inode,
+            rules,
+            ignored,
+            dir,
+            parent,
+        }))
+    }
+}
+
+/// 结构体，需要进入的目录
+#[derive(Clone, Copy)]
+struct DirId {
+    volume_id: u64,
+    inode: u64,
+    modified: Option<SystemTime>,
+}
+
+/// 结构体，已读取的目录条目，其中被选出的子条目有待分析
+struct DirListing {
+    entries: Vec<ChildEntry>,
+    /// 被选出的条目的下标及其是否被忽略
+    selected: Vec<(usize, bool)>,
+    errors: Vec<ScanError>,
+    /// 目录完整读取且启用了 --cache 时为其修改时间，分析完成后写入缓存
+    record: Option<SystemTime>,
 }
 
 impl AnalysisItem {
-    pub fn analyze(path: &Path, apparent: bool, root_dev: u64) -> Result<Self, Box<dyn Error>> {
-        let name: String = path
-            .file_name()
-            .unwrap_or(OsStr::new("."))
-            .to_string_lossy()
-            .to_string();
+    pub(crate) fn new(name: OsString) -> Self {
+        Self {
+            name,
+            apparent_size: 0,
+            allocated_size: 0,
+            children: None,
+            file_count: 0,
+            dir_count: 0,
+            errors: Vec::new(),
+            mount: None,
+            symlink: None,
+            partial: false,
+            filtered: false,
+            compacted: None,
+        }
+    }
+
+    pub fn analyze(path: &Path, context: &AnalysisContext) -> Result<Self, Box<dyn Error>> {
+        // 扫描根目录总会被进入，只有 --only-ignored 需要知道它本身是否被忽略
+        let ignored = context.ignore_rules.mode == IgnoreMode::OnlyIgnored
+            && context
+                .ignore_rules
+                .is_ignored(std::iter::empty(), path, true);
+        Self::analyze_entry(path, context, None, ignored, None)
+    }
+
+    /// ignored 表示该条目被忽略规则匹配或位于被忽略的目录中，
+    /// cached 为读取父目录时已取得的信息，不跟随符号链接。
+    /// 每一级目录都会递归经过这里，条目与祖先链放在堆上，其余工作交给不内联的函数，
+    /// 使很深的目录树也不会耗尽线程的栈
+    fn analyze_entry(
+        path: &Path,
+        context: &AnalysisContext,
+        parent: Option<&Ancestor>,
+        ignored: bool,
+        cached: Option<FileInfo>,
+    ) -> Result<Self, Box<dyn Error>> {
+        let name = path.file_name().unwrap_or(OsStr::new(".")).to_os_string();
+        let mut item = Box::new(AnalysisItem::new(name));
+        if let Some(id) = item.visit(path, context, parent, cached)? {
+            let current = Ancestor::open(path, context, parent, ignored, &item, id)?;
+            let listing = item.list_dir(path, context, &current, id.modified)?;
+            let results = Self::analyze_children(path, context, &current, &listing);
+            item.finish_dir(path, context, listing, results, id);
+        }
+        Ok(*item)
+    }
+
+    /// 读取条目的信息并计入文件的大小，返回需要进入的目录
+    #[inline(never)]
+    fn visit(
+        &mut self,
+        path: &Path,
+        context: &AnalysisContext,
+        parent: Option<&Ancestor>,
+        cached: Option<FileInfo>,
+    ) -> Result<Option<DirId>, Box<dyn Error>> {
+        match self.read_info(path, context, parent, cached)? {
+            FileInfo::Directory {
+                volume_id,
+                inode,
+                modified,
+            } => Ok(self
+                .should_enter(path, context, parent, volume_id, inode)
+                .then_some(DirId {
+                    volume_id,
+                    inode,
+                    modified,
+                })),
+            file_info => {
+                self.count_file(path, context, file_info);
+                Ok(None)
+            }
+        }
+    }
+
+    /// 读取条目的信息，为符号链接记录其指向，并按 --symlinks 决定是否跟随
+    fn read_info(
+        &mut self,
+        path: &Path,
+        context: &AnalysisContext,
+        parent: Option<&Ancestor>,
+        cached: Option<FileInfo>,
+    ) -> Result<FileInfo, Box<dyn Error>> {
+        // 除扫描根目录外，条目都相对父目录访问
+        let stat = |follow_symlinks| match (parent, path.file_name()) {
+            (Some(parent), Some(name)) => parent.dir.stat_child(name, follow_symlinks),
+            _ => FileInfo::from_path(path, follow_symlinks),
+        };
+        let mut file_info: FileInfo = match cached {
+            Some(info) => info,
+            None => stat(false)?,
+        };
+        if let FileInfo::Symlink { target, .. } = &file_info {
+            let followed = context.symlinks == SymlinkPolicy::Follow;
+            self.symlink = Some(SymbolicLink {
+                target: target.clone(),
+                followed,
+                cycle: false,
+                duplicate: false,
+            });
+            if followed {
+                file_info = stat(true)?;
+            }
+        }
+        Ok(file_info)
+    }
+
+    /// 判断是否进入该目录，挂载点、循环、中断与已进入过的目录只保留条目本身
+    fn should_enter(
+        &mut self,
+        path: &Path,
+        context: &AnalysisContext,
+        parent: Option<&Ancestor>,
+        volume_id: u64,
+        inode: u64,
+    ) -> bool {
+        let parent_dev = parent.map_or(context.root_dev, |parent| parent.volume_id);
+        if volume_id != parent_dev {
+            self.mount = Some(MountPoint::new(path, volume_id, context.cross_filesystems));
+        }
+        // 默认不进入其他文件系统，仅将挂载点作为叶子节点保留
+        if !context.cross_filesystems && self.mount.is_some() {
+            self.children = Some(Vec::new());
+            return false;
+        }
+        // 跟随符号链接回到祖先目录时停止，避免死循环
+        if parent.is_some_and(|parent| parent.contains(volume_id, inode)) {
+            if let Some(symlink) = &mut self.symlink {
+                symlink.followed = false;
+                symlink.cycle = true;
+            }
+            return false;
+        }
+        // 中断后遇到的目录保留为空目录
+        if context.interrupt.is_triggered() {
+            self.children = Some(Vec::new());
+            self.partial = true;
+            return false;
+        }
+        // 经其他符号链接或绑定挂载已进入过的目录不再重复计数
+        if !context.visited_dirs.first_visit(volume_id, inode) {
+            match &mut self.symlink {
+                Some(symlink) => {
+                    symlink.followed = false;
+                    symlink.duplicate = true;
+                }
+                None => self.children = Some(Vec::new()),
+            }
+            return false;
+        }
+        true
+    }
+
+    /// 读取目录的条目，或从缓存中取出，并选出需要分析的子条目
+    #[inline(never)]
+    fn list_dir(
+        &mut self,
+        path: &Path,
+        context: &AnalysisContext,
+        current: &Ancestor,
+        modified: Option<SystemTime>,
+    ) -> io::Result<DirListing> {
+        context.progress.visit_dir(path);
+        let mut errors = Vec::new();
+        let cache = context.cache.as_ref();
+        let reused =
+            cache.and_then(|cache| cache.reuse(path, current.volume_id, current.inode, modified));
+        let from_cache = reused.is_some();
+        let mut entries = match reused {
+            Some(entries) => entries,
+            None => current.dir.read_entries(&mut errors)?,
+        };
+        // 只缓存完整读取的目录
+        let record = modified.filter(|_| cache.is_some() && errors.is_empty());
+        // 缓存中需要文件的信息，读取目录时一并取得，子条目无需再次读取
+        if record.is_some() && !from_cache {
+            let stat_entry = |entry: &mut ChildEntry| {
+                if entry.kind != Some(EntryKind::Dir) {
+                    entry.info = current
+                        .dir
+                        .stat_child(&entry.name, false)
+                        .ok()
+                        .filter(|info| !matches!(info, FileInfo::Directory { .. }));
+                }
+            };
+            match context.io_mode {
+                IoMode::Sequential => entries.iter_mut().for_each(stat_entry),
+                IoMode::Parallel => entries.par_iter_mut().for_each(stat_entry),
+            }
+        }
+        let selected = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                Self::select_entry(entry, context, current).map(|ignored| (index, ignored))
+            })
+            .collect::<Vec<_>>();
+        self.filtered = selected.len() < entries.len();
+        Ok(DirListing {
+            entries,
+            selected,
+            errors,
+            record,
+        })
+    }
 
-        let file_info: FileInfo = FileInfo::from_path(path, apparent)?;
+    /// 逐个或并行分析选出的子条目，中断后未读取的条目为 None
+    fn analyze_children(
+        path: &Path,
+        context: &AnalysisContext,
+        current: &Ancestor,
+        listing: &DirListing,
+    ) -> Vec<Option<Result<Self, ScanError>>> {
+        let analyze = |&(index, ignored): &(usize, bool)| {
+            Self::analyze_child(path, context, current, &listing.entries[index], ignored)
+        };
+        match context.io_mode {
+            IoMode::Sequential => listing.selected.iter().map(analyze).collect(),
+            IoMode::Parallel => listing.selected.par_iter().map(analyze).collect(),
+        }
+    }
 
+    #[inline(never)]
+    fn analyze_child(
+        path: &Path,
+        context: &AnalysisContext,
+        parent: &Ancestor,
+        entry: &ChildEntry,
+        ignored: bool,
+    ) -> Option<Result<Self, ScanError>> {
+        if context.interrupt.is_triggered() {
+            return None;
+        }
+        let entry_path = path.join(&entry.name);
+        let result = Self::analyze_entry(
+            &entry_path,
+            context,
+            Some(parent),
+            ignored,
+            entry.info.clone(),
+        );
+        Some(result.map_err(|error| ScanError::from_boxed(&entry_path, error.as_ref())))
+    }
+
+    /// 汇总子条目的结果，写入缓存并记录最大的目录
+    #[inline(never)]
+    fn finish_dir(
+        &mut self,
+        path: &Path,
+        context: &AnalysisContext,
+        listing: DirListing,
+        results: Vec<Option<Result<Self, ScanError>>>,
+        id: DirId,
+    ) {
+        let DirListing {
+            entries,
+            mut errors,
+            record,
+            ..
+        } = listing;
+        let mut sub_items = Vec::with_capacity(results.len());
+        for result in results {
+            match result {
+                Some(Ok(sub_item)) => {
+                    self.partial |= sub_item.partial;
+                    sub_items.push(sub_item);
+                }
+                Some(Err(error)) => errors.push(error),
+                // 中断后未读取的条目
+                None => self.partial = true,
+            }
+        }
+        if let Some((cache, modified)) = context.cache.as_ref().zip(record) {
+            cache.record(
+                path.to_path_buf(),
+                id.volume_id,
+                id.inode,
+                modified,
+                entries,
+            );
+        }
+
+        self.set_children(sub_items, context.size_mode);
+        context.progress.add_errors(errors.len());
+        self.errors = errors;
+        // 目录直接包含的文件，不含子目录中的内容
+        if context.top_dirs.is_enabled() {
+            let direct_size = self
+                .children
+                .iter()
+                .flatten()
+                .filter(|child| !child.is_dir())
+                .map(|child| child.size(context.size_mode))
+                .sum();
+            context.top_dirs.offer(direct_size, path.to_path_buf());
+        }
+        if let Some(compact) = &context.compact {
+            compact.absorb(self);
+        }
+    }
+
+    /// 计入文件与符号链接本身的大小
+    fn count_file(&mut self, path: &Path, context: &AnalysisContext, file_info: FileInfo) {
         match file_info {
-            FileInfo::Directory { volume_id } => {
-                if volume_id != root_dev {
-                    return Err("Filesystem boundary crossed.".into());
+            FileInfo::File {
+                apparent_size,
+                allocated_size,
+                volume_id,
+                inode,
+                nlink,
+                uid,
+                gid,
+                modified,
+                accessed,
+            } => {
+                // 不满足 --older-than 与 --newer-than 的文件保留条目但不计入大小
+                let age = context.ages.age(modified, accessed);
+                if !context.ages.matches(age) {
+                    self.filtered = true;
+                    context.progress.visit_file(0);
+                    return;
+                }
+                // 同一 inode 的多个硬链接只计数一次
+                let size = context.size_mode.primary(apparent_size, allocated_size);
+                if !context
+                    .hard_links
+                    .is_duplicate(volume_id, inode, nlink, size)
+                {
+                    self.apparent_size = apparent_size;
+                    self.allocated_size = allocated_size;
+                    context.top_files.offer(size, path.to_path_buf());
+                    context.file_types.add(path, size);
+                    context.owners.add(uid, size, path, &context.root);
+                    context.groups.add(gid, size, path, &context.root);
+                    context.ages.record(age, size);
                 }
+            }
+            FileInfo::Symlink {
+                apparent_size,
+                allocated_size,
+                ..
+            } => {
+                self.apparent_size = apparent_size;
+                self.allocated_size = allocated_size;
+            }
+            FileInfo::Directory { .. } => unreachable!("directories are not counted as files"),
+        }
+        context.progress.visit_file(self.size(context.size_mode));
+    }
+
+    /// 在进入子条目之前应用符号链接策略、--exclude 与 --include 以及忽略规则，
+    /// 被排除的目录不会被遍历。返回该条目是否被忽略
+    fn select_entry(
+        entry: &ChildEntry,
+        context: &AnalysisContext,
+        parent: &Ancestor,
+    ) -> Option<bool> {
+        let is_symlink = entry.kind == Some(EntryKind::Symlink);
+        if is_symlink && context.symlinks == SymlinkPolicy::Skip {
+            return None;
+        }
+        let is_dir = entry.kind == Some(EntryKind::Dir)
+            || (is_symlink
+                && context.symlinks == SymlinkPolicy::Follow
+                && parent.dir.child_is_dir(&entry.name));
 
-                let sub_entries = fs::read_dir(path)?
-                    .filter_map(Result::ok)
-                    .collect::<Vec<_>>();
+        let path = parent.dir.path().join(&entry.name);
+        let relative = path.strip_prefix(&context.root).unwrap_or(&path);
+        if context
+            .filter
+            .is_excluded(Path::new(&entry.name), relative, is_dir)
+        {
+            return None;
+        }
 
-                let mut sub_items = sub_entries
-                    .par_iter()
-                    .filter_map(|entry| {
-                        AnalysisItem::analyze(&entry.path(), apparent, root_dev).ok()
-                    })
-                    .collect::<Vec<_>>();
+        let rules = &context.ignore_rules;
+        let ignored = match rules.mode {
+            IgnoreMode::Off => false,
+            _ => parent.ignored || rules.is_ignored(parent.rules(), &path, is_dir),
+        };
+        // --only-ignored 仍需进入未被忽略的目录，以找到其中被忽略的条目
+        let skipped = match rules.mode {
+            IgnoreMode::Off => false,
+            IgnoreMode::Respect => ignored,
+            IgnoreMode::OnlyIgnored => !ignored && !is_dir,
+        };
+        if skipped {
+            rules.add_skipped();
+            return None;
+        }
+        Some(ignored)
+    }
 
-                sub_items.sort_unstable_by(|a, b| a.disk_size.cmp(&b.disk_size).reverse());
+    /// 设置目录的子项，按大小降序排列并汇总大小与数量
+    pub(crate) fn set_children(&mut self, mut children: Vec<AnalysisItem>, size_mode: SizeMode) {
+        children.sort_unstable_by_key(|item| Reverse(item.size(size_mode)));
 
-                Ok(AnalysisItem {
-                    name,
-                    disk_size: sub_items.iter().map(|di| di.disk_size).sum(),
-                    children: Some(sub_items),
-                })
+        self.apparent_size = children.iter().map(|di| di.apparent_size).sum();
+        self.allocated_size = children.iter().map(|di| di.allocated_size).sum();
+        self.file_count = 0;
+        self.dir_count = 0;
+        for child in &children {
+            match child.is_dir() {
+                true => self.dir_count += child.dir_count + 1,
+                false => self.file_count += 1,
             }
-            FileInfo::File { size, .. } => Ok(AnalysisItem {
-                name,
-                disk_size: size,
-                children: None,
-            }),
+            self.file_count += child.file_count;
+        }
+        self.children = Some(children);
+    }
+
+    /// 按子项下标组成的路径移除一个后代条目，并从沿途每一级目录中减去其大小与数量
+    pub fn remove_descendant(&mut self, path: &[usize]) -> Option<AnalysisItem> {
+        let (&index, rest) = path.split_first()?;
+        let children = self.children.as_mut()?;
+        let removed = match rest.is_empty() {
+            true => (index < children.len()).then(|| children.remove(index))?,
+            false => children.get_mut(index)?.remove_descendant(rest)?,
+        };
+        self.apparent_size -= removed.apparent_size;
+        self.allocated_size -= removed.allocated_size;
+        match removed.is_dir() {
+            true => self.dir_count -= removed.dir_count + 1,
+            false => self.file_count -= 1,
+        }
+        self.file_count -= removed.file_count;
+        Some(removed)
+    }
+
+    /// 按名称组成的相对路径替换一个后代条目，new 为 None 时只移除，不存在时添加。
+    /// 沿途每一级目录重新汇总大小与数量并排序，返回被替换的条目；路径中的目录不存在时不做修改
+    pub fn replace_descendant(
+        &mut self,
+        names: &[&OsStr],
+        new: Option<AnalysisItem>,
+        size_mode: SizeMode,
+    ) -> Option<AnalysisItem> {
+        let (&name, rest) = names.split_first()?;
+        let mut children = self.children.take()?;
+        let replaced = match rest.is_empty() {
+            true => {
+                let old = children
+                    .iter()
+                    .position(|child| child.name == name)
+                    .map(|index| children.remove(index));
+                children.extend(new);
+                old
+            }
+            false => children
+                .iter_mut()
+                .find(|child| child.name == name)
+                .and_then(|child| child.replace_descendant(rest, new, size_mode)),
+        };
+        self.set_children(children, size_mode);
+        replaced
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.children.is_some()
+    }
+
+    /// 按大小类型取用于排序与百分比的大小
+    pub fn size(&self, size_mode: SizeMode) -> u64 {
+        size_mode.primary(self.apparent_size, self.allocated_size)
+    }
+
+    /// 整棵树中无法读取的条目数
+    pub fn error_count(&self) -> usize {
+        self.errors.len()
+            + self
+                .children
+                .iter()
+                .flatten()
+                .map(AnalysisItem::error_count)
+                .sum::<usize>()
+    }
+
+    /// 按深度优先顺序收集整棵树中无法读取的条目
+    pub fn collect_errors(&self) -> Vec<&ScanError> {
+        let mut errors = self.errors.iter().collect::<Vec<_>>();
+        for child in self.children.iter().flatten() {
+            errors.extend(child.collect_errors());
         }
+        errors
     }
 }
@@ -0,0 +1,355 @@
+//! `--compact` 使用的紧凑树形结构。
+//!
+//! 扫描时每读完一个目录，就把它的子条目从 `AnalysisItem` 移入一个共享的 arena：
+//! 节点按目录连续存放在一个 `Vec` 中，以下标相互引用；所有名称依次存放在同一块缓冲区中。
+//!
+//! 每个条目的内存开销（64 位平台）：
+//!
+//! ```txt
+//! 节点         48 字节（BYTES_PER_NODE）
+//! 名称         名称的字节数，不再单独分配
+//! 符号链接     另加目标路径，仅符号链接
+//! 挂载点       另加设备名称，仅挂载点
+//! ```
+//!
+//! 而 `AnalysisItem` 每个条目约 200 字节，另有名称的堆分配。
+//! 下标为 u32，一棵树最多约 40 亿个条目，名称总长不超过 4 GiB。
+
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::mem;
+use std::sync::Mutex;
+
+use crate::methods::size_fraction;
+use crate::struct_define::analysis_item::AnalysisItem;
+use crate::struct_define::config::{Arguments, SizeMode};
+use crate::struct_define::mount_point::MountPoint;
+use crate::struct_define::scan_error::ScanError;
+use crate::struct_define::symbolic_link::SymbolicLink;
+
+/// 每个节点占用的字节数，不含名称
+pub const BYTES_PER_NODE: usize = mem::size_of::<Node>();
+
+const _: () = assert!(BYTES_PER_NODE == 48);
+
+const FLAG_DIR: u8 = 1;
+const FLAG_PARTIAL: u8 = 1 << 1;
+const FLAG_FOLDED: u8 = 1 << 2;
+
+/// 尚未被父目录移入 arena 的节点
+const NO_PARENT: u32 = u32::MAX;
+
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    apparent_size: u64,
+    allocated_size: u64,
+    /// 子树中的文件数与目录数，不含自身；合并的小文件节点记录合并的文件数
+    file_count: u32,
+    dir_count: u32,
+    name_start: u32,
+    parent: u32,
+    /// 子节点连续存放
+    first_child: u32,
+    child_count: u32,
+    name_len: u16,
+    flags: u8,
+}
+
+/// 结构体，目录的子节点在 arena 中的位置
+#[derive(Debug, Clone, Copy)]
+pub struct ChildRange {
+    first: u32,
+    count: u32,
+}
+
+/// 结构体，符号链接与挂载点等少见的附加信息
+#[derive(Debug, Clone, Default)]
+struct Extra {
+    symlink: Option<SymbolicLink>,
+    mount: Option<MountPoint>,
+}
+
+/// 结构体，以 arena 存放的扫描结果
+#[derive(Debug, Default)]
+pub struct CompactTree {
+    nodes: Vec<Node>,
+    names: Vec<u8>,
+    extras: HashMap<u32, Extra>,
+    /// 整棵树中无法读取的条目
+    errors: Vec<ScanError>,
+    root: u32,
+}
+
+impl CompactTree {
+    pub fn root(&self) -> NodeRef<'_> {
+        self.node(self.root)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// 节点、名称与附加信息占用的字节数
+    pub fn memory_usage(&self) -> usize {
+        let extras = self.extras.values().map(|extra| {
+            mem::size_of::<(u32, Extra)>()
+                + extra
+                    .symlink
+                    .as_ref()
+                    .map_or(0, |symlink| symlink.target.as_os_str().len())
+                + extra.mount.as_ref().map_or(0, |mount| mount.source.len())
+        });
+        self.nodes.capacity() * BYTES_PER_NODE + self.names.capacity() + extras.sum::<usize>()
+    }
+
+    pub fn errors(&self) -> &[ScanError] {
+        &self.errors
+    }
+
+    /// 按 --max-depth 与 --min-percent 只将需要显示的部分还原为 `AnalysisItem`，
+    /// 以便复用 `show_disk_analyze_result`。所有错误都记录在根目录上
+    pub fn to_item(&self, config: &Arguments) -> AnalysisItem {
+        let mut item = self.root().to_item(0, config);
+        item.errors = self.errors.clone();
+        item
+    }
+
+    fn node(&self, index: u32) -> NodeRef<'_> {
+        NodeRef { tree: self, index }
+    }
+
+    fn push(&mut self, item: AnalysisItem, folded: bool) -> u32 {
+        let index =
+            u32::try_from(self.nodes.len()).expect("compact tree is limited to u32 entries");
+        let name = item.name.as_encoded_bytes();
+        let name_start =
+            u32::try_from(self.names.len()).expect("compact tree names are limited to 4 GiB");
+        let name_len = u16::try_from(name.len()).expect("file names are shorter than 64 KiB");
+        self.names.extend_from_slice(name);
+
+        let range = item.compacted.unwrap_or(ChildRange { first: 0, count: 0 });
+        for child in range.first..range.first + range.count {
+            self.nodes[child as usize].parent = index;
+        }
+        let mut flags = 0;
+        if item.is_dir() {
+            flags |= FLAG_DIR;
+        }
+        if item.partial {
+            flags |= FLAG_PARTIAL;
+        }
+        if folded {
+            flags |= FLAG_FOLDED;
+        }
+        self.nodes.push(Node {
+            apparent_size: item.apparent_size,
+            allocated_size: item.allocated_size,
+            file_count: item.file_count.min(u32::MAX as u64) as u32,
+            dir_count: item.dir_count.min(u32::MAX as u64) as u32,
+            name_start,
+            parent: NO_PARENT,
+            first_child: range.first,
+            child_count: range.count,
+            name_len,
+            flags,
+        });
+        if item.symlink.is_some() || item.mount.is_some() {
+            let extra = Extra {
+                symlink: item.symlink,
+                mount: item.mount,
+            };
+            self.extras.insert(index, extra);
+        }
+        self.errors.extend(item.errors);
+        index
+    }
+}
+
+/// 结构体，CompactTree 中的一个节点，提供与 `AnalysisItem` 相近的访问方法
+#[derive(Debug, Clone, Copy)]
+pub struct NodeRef<'a> {
+    tree: &'a CompactTree,
+    index: u32,
+}
+
+impl<'a> NodeRef<'a> {
+    fn node(&self) -> &'a Node {
+        &self.tree.nodes[self.index as usize]
+    }
+
+    pub fn name(&self) -> &'a OsStr {
+        let node = self.node();
+        let start = node.name_start as usize;
+        let bytes = &self.tree.names[start..start + node.name_len as usize];
+        // SAFETY: 名称由 OsStr::as_encoded_bytes 完整写入，未被截断或拼接
+        unsafe { OsStr::from_encoded_bytes_unchecked(bytes) }
+    }
+
+    pub fn apparent_size(&self) -> u64 {
+        self.node().apparent_size
+    }
+
+    pub fn allocated_size(&self) -> u64 {
+        self.node().allocated_size
+    }
+
+    pub fn size(&self, size_mode: SizeMode) -> u64 {
+        size_mode.primary(self.apparent_size(), self.allocated_size())
+    }
+
+    pub fn file_count(&self) -> u64 {
+        self.node().file_count as u64
+    }
+
+    pub fn dir_count(&self) -> u64 {
+        self.node().dir_count as u64
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.node().flags & FLAG_DIR != 0
+    }
+
+    /// 扫描被中断时该目录中有未读取的条目
+    pub fn is_partial(&self) -> bool {
+        self.node().flags & FLAG_PARTIAL != 0
+    }
+
+    /// 由 --fold-small-files 合并的小文件
+    pub fn is_folded(&self) -> bool {
+        self.node().flags & FLAG_FOLDED != 0
+    }
+
+    pub fn symlink(&self) -> Option<&'a SymbolicLink> {
+        self.extra()?.symlink.as_ref()
+    }
+
+    pub fn mount(&self) -> Option<&'a MountPoint> {
+        self.extra()?.mount.as_ref()
+    }
+
+    pub fn parent(&self) -> Option<NodeRef<'a>> {
+        match self.node().parent {
+            NO_PARENT => None,
+            parent => Some(self.tree.node(parent)),
+        }
+    }
+
+    /// 按大小降序排列的子节点
+    pub fn children(&self) -> impl Iterator<Item = NodeRef<'a>> + 'a {
+        let tree = self.tree;
+        let node = self.node();
+        (node.first_child..node.first_child + node.child_count).map(move |index| tree.node(index))
+    }
+
+    fn extra(&self) -> Option<&'a Extra> {
+        self.tree.extras.get(&self.index)
+    }
+
+    fn to_item(self, depth: usize, config: &Arguments) -> AnalysisItem {
+        let mut item = AnalysisItem::new(self.name().to_os_string());
+        item.apparent_size = self.apparent_size();
+        item.allocated_size = self.allocated_size();
+        item.file_count = self.file_count();
+        item.dir_count = self.dir_count();
+        item.partial = self.is_partial();
+        item.symlink = self.symlink().cloned();
+        item.mount = self.mount().cloned();
+        if self.is_dir() {
+            let parent_size = self.size(config.size_mode());
+            let visible = |child: &NodeRef| {
+                let fraction = match parent_size {
+                    0 => 0.0,
+                    _ => 100.0 * child.size(config.size_mode()) as f64 / parent_size as f64,
+                };
+                fraction > config.min_percent || child.mount().is_some()
+            };
+            let children = match depth < config.max_depth {
+                true => self
+                    .children()
+                    .filter(visible)
+                    .map(|child| child.to_item(depth + 1, config))
+                    .collect(),
+                false => Vec::new(),
+            };
+            item.children = Some(children);
+        }
+        item
+    }
+}
+
+/// 结构体，扫描过程中逐个目录构建 CompactTree
+#[derive(Debug)]
+pub struct CompactBuilder {
+    size_mode: SizeMode,
+    /// 占父目录的百分比不超过该值的文件合并为一个节点
+    fold_percent: Option<f64>,
+    tree: Mutex<CompactTree>,
+}
+
+impl CompactBuilder {
+    pub fn new(size_mode: SizeMode, fold_percent: Option<f64>) -> Self {
+        Self {
+            size_mode,
+            fold_percent,
+            tree: Mutex::new(CompactTree::default()),
+        }
+    }
+
+    /// 将已读完的目录的子条目移入 arena，目录本身只保留它们的位置
+    pub(crate) fn absorb(&self, item: &mut AnalysisItem) {
+        let Some(children) = item.children.replace(Vec::new()) else {
+            return;
+        };
+        let mut kept = Vec::with_capacity(children.len());
+        let mut folded = AnalysisItem::new(Default::default());
+        for child in children {
+            let small = self.fold_percent.is_some_and(|percent| {
+                size_fraction(&child, item, self.size_mode) <= percent
+                    && !child.is_dir()
+                    && child.symlink.is_none()
+            });
+            match small {
+                true => {
+                    folded.apparent_size += child.apparent_size;
+                    folded.allocated_size += child.allocated_size;
+                    folded.file_count += 1;
+                }
+                false => kept.push(child),
+            }
+        }
+
+        let mut tree = self.tree.lock().unwrap();
+        let first = tree.nodes.len() as u32;
+        let count = kept.len() as u32 + (folded.file_count > 0) as u32;
+        // 合并的节点按大小插入，保持子节点按大小降序
+        let folded_at = kept
+            .iter()
+            .position(|child| child.size(self.size_mode) < folded.size(self.size_mode))
+            .unwrap_or(kept.len());
+        let mut folded = (folded.file_count > 0).then_some(folded);
+        for (position, child) in kept.into_iter().enumerate() {
+            if position == folded_at {
+                if let Some(folded) = folded.take() {
+                    push_folded(&mut tree, folded);
+                }
+            }
+            tree.push(child, false);
+        }
+        if let Some(folded) = folded {
+            push_folded(&mut tree, folded);
+        }
+        item.compacted = Some(ChildRange { first, count });
+    }
+
+    /// 扫描结束后放入根目录
+    pub fn finish(self, root: AnalysisItem) -> CompactTree {
+        let mut tree = self.tree.into_inner().unwrap();
+        tree.root = tree.push(root, false);
+        tree
+    }
+}
+
+fn push_folded(tree: &mut CompactTree, mut folded: AnalysisItem) {
+    folded.name = format!("({} smaller files)", folded.file_count).into();
+    tree.push(folded, true);
+}
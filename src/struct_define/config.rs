@@ -1,6 +1,165 @@
 use std::path::PathBuf;
+use std::str::FromStr;
 use structopt::StructOpt;
 
+use crate::struct_define::file_age::{Age, AgeTime};
+use crate::struct_define::file_types::FileTypeMode;
+use crate::struct_define::ignore_rules::IgnoreMode;
+
+/// 枚举，符号链接的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymlinkPolicy {
+    /// 不显示也不计数
+    Skip,
+    /// 仅计数链接本身
+    Count,
+    /// 跟随链接分析其目标
+    Follow,
+}
+
+impl SymlinkPolicy {
+    pub const VARIANTS: [&'static str; 3] = ["skip", "count", "follow"];
+}
+
+impl FromStr for SymlinkPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(SymlinkPolicy::Skip),
+            "count" => Ok(SymlinkPolicy::Count),
+            "follow" => Ok(SymlinkPolicy::Follow),
+            _ => Err(format!("invalid symlink policy: {}", s)),
+        }
+    }
+}
+
+/// 枚举，结果的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// 终端中的彩色树形结构
+    Text,
+    Json,
+    /// 每个条目一行的表格
+    Csv,
+    Tsv,
+}
+
+impl OutputFormat {
+    pub const VARIANTS: [&'static str; 4] = ["text", "json", "csv", "tsv"];
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(format!("invalid output format: {}", s)),
+        }
+    }
+}
+
+/// 枚举，统计与显示的大小类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeMode {
+    /// 文件长度（du --apparent-size）
+    Apparent,
+    /// 实际占用的磁盘块
+    Allocated,
+    /// 同时显示两者，以实际占用排序
+    Both,
+}
+
+impl SizeMode {
+    pub const VARIANTS: [&'static str; 3] = ["apparent", "allocated", "both"];
+
+    /// 用于排序与计算百分比的大小
+    pub fn primary(self, apparent_size: u64, allocated_size: u64) -> u64 {
+        match self {
+            SizeMode::Apparent => apparent_size,
+            SizeMode::Allocated | SizeMode::Both => allocated_size,
+        }
+    }
+}
+
+impl FromStr for SizeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "apparent" => Ok(SizeMode::Apparent),
+            "allocated" => Ok(SizeMode::Allocated),
+            "both" => Ok(SizeMode::Both),
+            _ => Err(format!("invalid size mode: {}", s)),
+        }
+    }
+}
+
+/// 枚举，读取目录条目的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoMode {
+    /// 在同一线程中逐个读取，适合机械硬盘与 NFS
+    Sequential,
+    /// 在线程池中并行读取
+    Parallel,
+}
+
+impl IoMode {
+    pub const VARIANTS: [&'static str; 2] = ["sequential", "parallel"];
+}
+
+impl FromStr for IoMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sequential" => Ok(IoMode::Sequential),
+            "parallel" => Ok(IoMode::Parallel),
+            _ => Err(format!("invalid I/O mode: {}", s)),
+        }
+    }
+}
+
+/// 枚举，子命令
+#[derive(Debug, StructOpt)]
+pub enum Command {
+    /// Save the scan of the target directory for later comparison
+    Snapshot(SnapshotCommand),
+    /// Show how sizes changed between two snapshots
+    Diff {
+        /// The earlier snapshot
+        #[structopt(parse(from_os_str))]
+        old: PathBuf,
+        /// The later snapshot
+        /// [default: scan the directory of <old> again]
+        #[structopt(parse(from_os_str))]
+        new: Option<PathBuf>,
+    },
+    /// Find files with identical content in the target directory (text or json output)
+    Dupes {
+        /// Directory to search
+        /// [default: the target directory]
+        #[structopt(parse(from_os_str))]
+        dir: Option<PathBuf>,
+        /// Ignore files smaller than this many bytes
+        #[structopt(long = "min-size", default_value = "1")]
+        min_size: u64,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+pub enum SnapshotCommand {
+    /// Scan the target directory and save the whole tree with a timestamp ("-" for stdout)
+    Save {
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(name = "mrdu", about = "A simple command line disk analysis tool.")]
 pub struct Arguments {
@@ -18,13 +177,244 @@ pub struct Arguments {
     #[structopt(short = "p", long = "min-percent", default_value = "5")]
     pub min_percent: f64,
 
-    /// Apparent size on disk
-    // This would actually retrieve allocation size of files (AKA physical size on disk)
+    /// Which size to measure: apparent (file length), allocated (blocks on disk) or both
+    #[structopt(
+        short = "s",
+        long = "size-mode",
+        default_value = "allocated",
+        possible_values = &SizeMode::VARIANTS
+    )]
+    pub size_mode: SizeMode,
+
+    /// Apparent size, shorthand for `--size-mode apparent`
     #[structopt(short = "a", long = "apparent")]
     pub apparent: bool,
 
+    /// Descend into other filesystems mounted below the target directory
+    // By default mount points are shown as leaves and not scanned.
+    #[structopt(long = "cross-filesystems")]
+    pub cross_filesystems: bool,
+
+    /// How to treat symbolic links: skip them, count the link itself, or follow it
+    #[structopt(
+        long = "symlinks",
+        default_value = "count",
+        possible_values = &SymlinkPolicy::VARIANTS
+    )]
+    pub symlinks: SymlinkPolicy,
+
+    /// Follow symbolic links, shorthand for `--symlinks follow`
+    #[structopt(short = "L", long = "follow-symlinks")]
+    pub follow_symlinks: bool,
+
+    /// Skip entries whose name or relative path matches this glob (repeatable)
+    #[structopt(long = "exclude", number_of_values = 1)]
+    pub exclude: Vec<String>,
+
+    /// Only count files whose name or relative path matches this glob (repeatable)
+    #[structopt(long = "include", number_of_values = 1)]
+    pub include: Vec<String>,
+
+    /// Skip entries ignored by .gitignore, .ignore and .git/info/exclude files
+    #[structopt(long = "respect-gitignore", conflicts_with = "only-ignored")]
+    pub respect_gitignore: bool,
+
+    /// Only count entries ignored by .gitignore, .ignore and .git/info/exclude files
+    #[structopt(long = "only-ignored")]
+    pub only_ignored: bool,
+
+    /// Output format
+    #[structopt(
+        short = "o",
+        long = "output",
+        default_value = "text",
+        possible_values = &OutputFormat::VARIANTS
+    )]
+    pub output: OutputFormat,
+
+    /// Export the whole tree, ignoring --max-depth and --min-percent (json, csv and tsv)
+    #[structopt(long = "full")]
+    pub full: bool,
+
+    /// Write the analysis in ncdu's JSON export format to this file ("-" for stdout)
+    #[structopt(
+        long = "export-ncdu",
+        parse(from_os_str),
+        conflicts_with = "import-ncdu"
+    )]
+    pub export_ncdu: Option<PathBuf>,
+
+    /// Load an ncdu JSON export ("-" for stdin) and show it instead of scanning
+    #[structopt(long = "import-ncdu", parse(from_os_str))]
+    pub import_ncdu: Option<PathBuf>,
+
+    /// Browse the result in a full-screen terminal interface
+    #[structopt(short = "i", long = "interactive", conflicts_with = "export-ncdu")]
+    pub interactive: bool,
+
     /// Number of decimal places
     // The number of decimal places occupied by files or folders.
     #[structopt(short = "n", long = "precision", default_value = "2")]
     pub decimal_num: usize,
+
+    /// List the N largest files in the whole tree
+    #[structopt(long = "top-files", value_name = "N")]
+    pub top_files: Option<usize>,
+
+    /// List the N directories with the largest direct content (files directly inside them)
+    #[structopt(long = "top-dirs", value_name = "N")]
+    pub top_dirs: Option<usize>,
+
+    /// Report total size and count of files grouped by extension
+    #[structopt(long = "by-extension", conflicts_with = "by-content")]
+    pub by_extension: bool,
+
+    /// Report files grouped by the type detected from their first bytes, like file(1)
+    #[structopt(long = "by-content")]
+    pub by_content: bool,
+
+    /// Report total size and count of files per owning user
+    #[structopt(long = "by-owner")]
+    pub by_owner: bool,
+
+    /// Report total size and count of files per owning group
+    #[structopt(long = "by-group")]
+    pub by_group: bool,
+
+    /// With --by-owner or --by-group, also break the totals down per top-level directory
+    #[structopt(long = "per-directory")]
+    pub per_directory: bool,
+
+    /// Report how many bytes were modified (or accessed) less than a day, week, month, ... ago
+    #[structopt(long = "age-histogram")]
+    pub age_histogram: bool,
+
+    /// Which timestamp defines the age of a file
+    #[structopt(
+        long = "age-time",
+        default_value = "modified",
+        possible_values = &AgeTime::VARIANTS
+    )]
+    pub age_time: AgeTime,
+
+    /// Only count files at least this old, e.g. 90d (units: h, d, w, m = 30d, y = 365d)
+    #[structopt(long = "older-than", value_name = "AGE")]
+    pub older_than: Option<Age>,
+
+    /// Only count files younger than this, e.g. 2w
+    #[structopt(long = "newer-than", value_name = "AGE")]
+    pub newer_than: Option<Age>,
+
+    /// Keep the scan in a compact arena (48 bytes per entry plus its name) instead of a tree of
+    /// heap objects. Only text output is supported
+    #[structopt(long = "compact", conflicts_with_all = &["interactive", "export-ncdu", "import-ncdu"])]
+    pub compact: bool,
+
+    /// With --compact, merge the files of each directory that are below --min-percent into one entry
+    #[structopt(long = "fold-small-files", requires = "compact")]
+    pub fold_small_files: bool,
+
+    /// Number of threads used to scan in parallel I/O mode
+    /// [default: one per CPU]
+    #[structopt(long = "threads", value_name = "N")]
+    pub threads: Option<usize>,
+
+    /// Read directories one at a time (sequential) or from a pool of --threads threads (parallel)
+    #[structopt(
+        long = "io-mode",
+        default_value = "parallel",
+        possible_values = &IoMode::VARIANTS
+    )]
+    pub io_mode: IoMode,
+
+    /// Read directories by their full path instead of the Linux openat/fstatat/getdents64 fast path
+    #[structopt(long = "portable-walk")]
+    pub portable_walk: bool,
+
+    /// Keep directory listings in FILE and reuse those whose modification time has not changed on
+    /// the next scan. Files changed in place do not update their directory; use --verify then
+    #[structopt(
+        long = "cache",
+        value_name = "FILE",
+        parse(from_os_str),
+        conflicts_with = "import-ncdu"
+    )]
+    pub cache: Option<PathBuf>,
+
+    /// With --cache, read every directory again and rewrite the cache
+    #[structopt(long = "verify", requires = "cache")]
+    pub verify: bool,
+
+    /// Keep watching the target directory after the scan and redraw the tree as it changes (Linux)
+    #[structopt(
+        long = "watch",
+        conflicts_with_all = &[
+            "interactive",
+            "compact",
+            "export-ncdu",
+            "import-ncdu",
+            "respect-gitignore",
+            "only-ignored"
+        ]
+    )]
+    pub watch: bool,
+
+    /// With --watch, seconds between redraws
+    /// [default: 2]
+    #[structopt(long = "interval", value_name = "SECS", requires = "watch")]
+    pub interval: Option<u64>,
+
+    /// Print every entry that could not be read to stderr
+    #[structopt(short = "e", long = "show-errors")]
+    pub show_errors: bool,
+
+    #[structopt(subcommand)]
+    pub command: Option<Command>,
+}
+
+impl Arguments {
+    /// 扫描使用的线程数，顺序读取时只使用当前线程
+    pub fn scan_threads(&self) -> usize {
+        match self.io_mode {
+            IoMode::Sequential => 1,
+            IoMode::Parallel => match self.threads {
+                Some(threads) if threads > 0 => threads,
+                _ => rayon::current_num_threads(),
+            },
+        }
+    }
+
+    /// `--follow-symlinks` 优先于 `--symlinks`
+    pub fn symlink_policy(&self) -> SymlinkPolicy {
+        if self.follow_symlinks {
+            SymlinkPolicy::Follow
+        } else {
+            self.symlinks
+        }
+    }
+
+    pub fn ignore_mode(&self) -> IgnoreMode {
+        match (self.respect_gitignore, self.only_ignored) {
+            (true, _) => IgnoreMode::Respect,
+            (_, true) => IgnoreMode::OnlyIgnored,
+            _ => IgnoreMode::Off,
+        }
+    }
+
+    pub fn file_type_mode(&self) -> FileTypeMode {
+        match (self.by_extension, self.by_content) {
+            (true, _) => FileTypeMode::Extension,
+            (_, true) => FileTypeMode::Content,
+            _ => FileTypeMode::Off,
+        }
+    }
+
+    /// `--apparent` 优先于 `--size-mode`
+    pub fn size_mode(&self) -> SizeMode {
+        if self.apparent {
+            SizeMode::Apparent
+        } else {
+            self.size_mode
+        }
+    }
 }
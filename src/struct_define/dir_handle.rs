@@ -0,0 +1,317 @@
+use std::error::Error;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::struct_define::file_info::FileInfo;
+use crate::struct_define::scan_error::ScanError;
+
+/// 枚举，读取目录时即可得到的条目类型，无需 stat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Dir,
+    Symlink,
+    /// 普通文件、FIFO、设备等
+    Other,
+}
+
+/// 结构体，目录中的一个条目
+#[derive(Debug)]
+pub struct ChildEntry {
+    pub name: OsString,
+    /// 无法确定类型时为 None
+    pub kind: Option<EntryKind>,
+    /// 已经读取的信息，如来自 --cache
+    pub info: Option<FileInfo>,
+}
+
+/// 结构体，正在读取的目录。
+/// Linux 上持有目录的文件描述符，子条目通过 openat、fstatat 与 getdents64 相对它访问，
+/// 不必每次由内核从根解析完整路径；其他平台或文件描述符不足时按完整路径访问
+#[derive(Debug)]
+pub struct DirHandle {
+    path: PathBuf,
+    #[cfg(target_os = "linux")]
+    fd: Option<std::os::fd::OwnedFd>,
+}
+
+impl DirHandle {
+    /// 打开扫描的根目录，fast 为 false 时始终按路径访问
+    pub fn open(path: &Path, fast: bool) -> io::Result<Self> {
+        #[cfg(target_os = "linux")]
+        if fast {
+            return linux::open(None, path, path.as_os_str(), true).map(|fd| Self {
+                path: path.to_path_buf(),
+                fd,
+            });
+        }
+        let _ = fast;
+        Ok(Self::portable(path))
+    }
+
+    fn portable(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            #[cfg(target_os = "linux")]
+            fd: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 打开子目录，follow 为 false 时子目录被替换为符号链接会报错
+    pub fn open_child(&self, name: &OsStr, follow: bool) -> io::Result<Self> {
+        let path = self.path.join(name);
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = &self.fd {
+            return linux::open(Some(fd), &path, name, follow).map(|fd| Self { path, fd });
+        }
+        let _ = follow;
+        Ok(Self::portable(&path))
+    }
+
+    /// 读取目录中除 `.` 与 `..` 外的条目，读取中途出错时记录错误并返回已读取的部分
+    pub fn read_entries(&self, errors: &mut Vec<ScanError>) -> io::Result<Vec<ChildEntry>> {
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = &self.fd {
+            return linux::read_entries(fd, &self.path, errors);
+        }
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&self.path)? {
+            match entry {
+                Ok(entry) => entries.push(ChildEntry {
+                    kind: entry.file_type().ok().map(|kind| match kind {
+                        kind if kind.is_dir() => EntryKind::Dir,
+                        kind if kind.is_symlink() => EntryKind::Symlink,
+                        _ => EntryKind::Other,
+                    }),
+                    name: entry.file_name(),
+                    info: None,
+                }),
+                Err(error) => errors.push(ScanError::new(&self.path, &error)),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// 子条目的信息，follow_symlinks 为 true 时返回符号链接所指向目标的信息
+    pub fn stat_child(
+        &self,
+        name: &OsStr,
+        follow_symlinks: bool,
+    ) -> Result<FileInfo, Box<dyn Error>> {
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = &self.fd {
+            return Ok(linux::stat_at(fd, name, follow_symlinks)?);
+        }
+        FileInfo::from_path(&self.path.join(name), follow_symlinks)
+    }
+
+    /// 子条目是否为目录，跟随符号链接
+    pub fn child_is_dir(&self, name: &OsStr) -> bool {
+        #[cfg(target_os = "linux")]
+        if let Some(fd) = &self.fd {
+            return matches!(
+                linux::stat_at(fd, name, true),
+                Ok(FileInfo::Directory { .. })
+            );
+        }
+        self.path.join(name).is_dir()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::ffi::{CString, OsStr, OsString};
+    use std::io;
+    use std::mem::MaybeUninit;
+    use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    use super::{ChildEntry, EntryKind};
+    use crate::struct_define::file_info::FileInfo;
+    use crate::struct_define::scan_error::ScanError;
+
+    /// getdents64 每次读取的缓冲区大小
+    const DIRENT_BUFFER: usize = 64 * 1024;
+
+    fn c_name(name: &OsStr) -> io::Result<CString> {
+        CString::new(name.as_bytes())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+    }
+
+    /// 打开目录。文件描述符用尽时返回 None，改为按路径访问该目录
+    pub fn open(
+        parent: Option<&OwnedFd>,
+        path: &Path,
+        name: &OsStr,
+        follow: bool,
+    ) -> io::Result<Option<OwnedFd>> {
+        let mut flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        if !follow {
+            flags |= libc::O_NOFOLLOW;
+        }
+        let (dir_fd, name) = match parent {
+            Some(parent) => (parent.as_raw_fd(), c_name(name)?),
+            None => (libc::AT_FDCWD, c_name(path.as_os_str())?),
+        };
+        // SAFETY: name 为以 NUL 结尾的字符串，成功时返回的文件描述符归调用者所有
+        let fd = unsafe { libc::openat(dir_fd, name.as_ptr(), flags) };
+        if fd < 0 {
+            let error = io::Error::last_os_error();
+            return match error.raw_os_error() {
+                Some(libc::EMFILE) | Some(libc::ENFILE) => Ok(None),
+                _ => Err(error),
+            };
+        }
+        // SAFETY: fd 刚由 openat 返回且未被其他对象持有
+        Ok(Some(unsafe { OwnedFd::from_raw_fd(fd) }))
+    }
+
+    pub fn read_entries(
+        fd: &OwnedFd,
+        path: &Path,
+        errors: &mut Vec<ScanError>,
+    ) -> io::Result<Vec<ChildEntry>> {
+        let mut entries = Vec::new();
+        let mut buffer = vec![0u8; DIRENT_BUFFER];
+        loop {
+            // SAFETY: 内核最多写入 buffer.len() 字节
+            let read = unsafe {
+                libc::syscall(
+                    libc::SYS_getdents64,
+                    fd.as_raw_fd(),
+                    buffer.as_mut_ptr(),
+                    buffer.len(),
+                )
+            };
+            if read < 0 {
+                let error = io::Error::last_os_error();
+                match entries.is_empty() {
+                    true => return Err(error),
+                    false => {
+                        errors.push(ScanError::new(path, &error));
+                        return Ok(entries);
+                    }
+                }
+            }
+            if read == 0 {
+                return Ok(entries);
+            }
+            let mut offset = 0;
+            while offset < read as usize {
+                // struct linux_dirent64 { u64 d_ino; i64 d_off; u16 d_reclen; u8 d_type; char d_name[]; }
+                let record = &buffer[offset..];
+                let length = u16::from_ne_bytes([record[16], record[17]]) as usize;
+                let kind = record[18];
+                let name = &record[19..length];
+                let name = &name[..name
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .unwrap_or(name.len())];
+                offset += length;
+                if name == b"." || name == b".." {
+                    continue;
+                }
+                let name = OsString::from_vec(name.to_vec());
+                let kind = match kind {
+                    libc::DT_DIR => Some(EntryKind::Dir),
+                    libc::DT_LNK => Some(EntryKind::Symlink),
+                    // 部分文件系统不提供类型，与 DirEntry::file_type 一样改用 stat
+                    libc::DT_UNKNOWN => match stat_at(fd, &name, false) {
+                        Ok(FileInfo::Directory { .. }) => Some(EntryKind::Dir),
+                        Ok(FileInfo::Symlink { .. }) => Some(EntryKind::Symlink),
+                        Ok(FileInfo::File { .. }) => Some(EntryKind::Other),
+                        Err(_) => None,
+                    },
+                    _ => Some(EntryKind::Other),
+                };
+                entries.push(ChildEntry {
+                    name,
+                    kind,
+                    info: None,
+                });
+            }
+        }
+    }
+
+    pub fn stat_at(fd: &OwnedFd, name: &OsStr, follow_symlinks: bool) -> io::Result<FileInfo> {
+        let c_name = c_name(name)?;
+        let flags = match follow_symlinks {
+            true => 0,
+            false => libc::AT_SYMLINK_NOFOLLOW,
+        };
+        let mut stat = MaybeUninit::<libc::stat>::uninit();
+        // SAFETY: 成功时内核写入完整的 stat
+        let stat = unsafe {
+            if libc::fstatat(fd.as_raw_fd(), c_name.as_ptr(), stat.as_mut_ptr(), flags) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            stat.assume_init()
+        };
+        Ok(match stat.st_mode & libc::S_IFMT {
+            libc::S_IFDIR => FileInfo::Directory {
+                volume_id: stat.st_dev,
+                inode: stat.st_ino,
+                modified: system_time(stat.st_mtime, stat.st_mtime_nsec),
+            },
+            libc::S_IFLNK => FileInfo::Symlink {
+                target: read_link_at(fd, &c_name)?,
+                apparent_size: stat.st_size as u64,
+                allocated_size: stat.st_blocks as u64 * 512,
+            },
+            _ => FileInfo::File {
+                apparent_size: stat.st_size as u64,
+                // st_blocks 的单位固定为 512 字节
+                allocated_size: stat.st_blocks as u64 * 512,
+                volume_id: stat.st_dev,
+                inode: stat.st_ino,
+                nlink: stat.st_nlink,
+                uid: stat.st_uid,
+                gid: stat.st_gid,
+                modified: system_time(stat.st_mtime, stat.st_mtime_nsec),
+                accessed: system_time(stat.st_atime, stat.st_atime_nsec),
+            },
+        })
+    }
+
+    fn read_link_at(fd: &OwnedFd, name: &CString) -> io::Result<PathBuf> {
+        let mut buffer = vec![0u8; 256];
+        loop {
+            // SAFETY: 内核最多写入 buffer.len() 字节
+            let length = unsafe {
+                libc::readlinkat(
+                    fd.as_raw_fd(),
+                    name.as_ptr(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                )
+            };
+            if length < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // 写满缓冲区时目标可能被截断
+            if (length as usize) < buffer.len() {
+                buffer.truncate(length as usize);
+                return Ok(PathBuf::from(OsString::from_vec(buffer)));
+            }
+            buffer.resize(buffer.len() * 2, 0);
+        }
+    }
+
+    /// 与 Metadata::modified 一致，时间早于 1970 年时也能表示
+    fn system_time(secs: i64, nsecs: i64) -> Option<SystemTime> {
+        let nanos = Duration::from_nanos(nsecs as u64);
+        match secs >= 0 {
+            true => UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64) + nanos),
+            false => UNIX_EPOCH
+                .checked_sub(Duration::from_secs(secs.unsigned_abs()))?
+                .checked_add(nanos),
+        }
+    }
+}
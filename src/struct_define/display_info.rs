@@ -67,6 +67,29 @@ impl DisplayItemInfo {
             _ => Some(get_color(100, 255, 90)),
         }
     }
+
+    /// 按大小变化的方向选择颜色，delta 为该条目的变化量；
+    /// 此时 self.occupied_size 为变化量占整体变化量的百分比，不足 10% 时颜色变暗
+    pub fn display_growth_color(&self, delta: i64) -> Option<Color> {
+        let darken = |x: u8| (x as f32 * 0.5).round() as u8;
+        let get_color = |r: u8, g: u8, b: u8| {
+            if self.occupied_size >= 10.0 {
+                Color::Rgb(r, g, b)
+            } else {
+                Color::Rgb(darken(r), darken(g), darken(b))
+            }
+        };
+        match self.dir_level {
+            // Analyzed root directory, White
+            0 => Some(Color::Rgb(250, 250, 250)),
+            // Grown or added, Red
+            _ if delta > 0 => Some(get_color(255, 100, 100)),
+            // Shrunk or removed, Green
+            _ if delta < 0 => Some(get_color(100, 255, 90)),
+            // Unchanged, Gray
+            _ => Some(Color::Rgb(147, 147, 147)),
+        }
+    }
 }
 
 impl Default for DisplayItemInfo {
@@ -0,0 +1,171 @@
+use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use xxhash_rust::xxh3::Xxh3;
+
+use crate::struct_define::analysis_item::AnalysisItem;
+use crate::struct_define::config::IoMode;
+use crate::struct_define::file_info::FileInfo;
+use crate::struct_define::interrupt::Interrupt;
+
+/// 部分哈希读取文件开头与结尾的字节数
+const PARTIAL_BLOCK: u64 = 4096;
+
+/// 结构体，内容完全相同的一组文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateSet {
+    /// 每个文件的长度
+    pub size: u64,
+    /// 按路径排序
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateSet {
+    /// 只保留一份时可以释放的字节数
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+/// 函数，在扫描结果中查找内容相同的文件，依次按长度、首尾块的哈希与完整内容的哈希分组。
+/// 扫描时已将重复的硬链接计为 0 字节，这里再按 inode 去重，互为硬链接的文件不算作重复。
+/// 中断后不再读取文件，只返回已确认的重复文件。
+/// 与扫描一样按 io_mode 逐个或并行读取，并行时使用调用者所在的线程池
+pub fn find_duplicates(
+    item: &AnalysisItem,
+    root: &Path,
+    min_size: u64,
+    io_mode: IoMode,
+    interrupt: &Interrupt,
+) -> Vec<DuplicateSet> {
+    let mut by_size = HashMap::<u64, Vec<PathBuf>>::new();
+    collect_files(item, root.to_path_buf(), min_size.max(1), &mut by_size);
+
+    let candidates = by_size
+        .into_iter()
+        .filter(|(_, paths)| paths.len() > 1)
+        .map(|(size, paths)| (size, distinct_inodes(paths)))
+        .filter(|(_, paths)| paths.len() > 1)
+        .collect::<Vec<_>>();
+
+    let confirm = |(size, paths): (u64, Vec<PathBuf>)| {
+        let partial = regroup(paths, |path| partial_hash(path, size, interrupt));
+        partial
+            .into_iter()
+            // 小文件的首尾块已覆盖全部内容，无需再计算完整哈希
+            .flat_map(move |paths| match size <= 2 * PARTIAL_BLOCK {
+                true => vec![paths],
+                false => regroup(paths, |path| full_hash(path, interrupt)),
+            })
+            .map(move |mut paths| {
+                paths.sort();
+                DuplicateSet { size, paths }
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut sets = match io_mode {
+        IoMode::Sequential => candidates.into_iter().flat_map(confirm).collect::<Vec<_>>(),
+        IoMode::Parallel => candidates.into_par_iter().flat_map_iter(confirm).collect(),
+    };
+    sets.sort_by(|a, b| {
+        Reverse(a.wasted())
+            .cmp(&Reverse(b.wasted()))
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    sets
+}
+
+/// 函数，按文件长度收集普通文件，跳过符号链接与长度小于 min_size 的文件
+fn collect_files(
+    item: &AnalysisItem,
+    path: PathBuf,
+    min_size: u64,
+    by_size: &mut HashMap<u64, Vec<PathBuf>>,
+) {
+    match &item.children {
+        Some(children) => {
+            for child in children {
+                collect_files(child, path.join(&child.name), min_size, by_size);
+            }
+        }
+        None if item.symlink.is_none() && item.apparent_size >= min_size => {
+            by_size.entry(item.apparent_size).or_default().push(path);
+        }
+        None => {}
+    }
+}
+
+/// 函数，每个 inode 只保留一个路径
+fn distinct_inodes(paths: Vec<PathBuf>) -> Vec<PathBuf> {
+    let mut seen = HashSet::new();
+    paths
+        .into_iter()
+        .filter(|path| match FileInfo::from_path(path, false) {
+            Ok(FileInfo::File {
+                volume_id, inode, ..
+            }) => seen.insert((volume_id, inode)),
+            _ => false,
+        })
+        .collect()
+}
+
+/// 函数，按 key 将路径重新分组，只保留多于一个文件的组，无法读取的文件被丢弃
+fn regroup<K: Hash + Eq>(
+    paths: Vec<PathBuf>,
+    key: impl Fn(&Path) -> io::Result<K>,
+) -> Vec<Vec<PathBuf>> {
+    let mut groups = HashMap::<K, Vec<PathBuf>>::new();
+    for path in paths {
+        if let Ok(key) = key(&path) {
+            groups.entry(key).or_default().push(path);
+        }
+    }
+    groups
+        .into_values()
+        .filter(|paths| paths.len() > 1)
+        .collect()
+}
+
+/// 函数，中断后返回的错误，未读取的文件与无法读取的文件一样被丢弃
+fn check_interrupt(interrupt: &Interrupt) -> io::Result<()> {
+    match interrupt.is_triggered() {
+        true => Err(io::ErrorKind::Interrupted.into()),
+        false => Ok(()),
+    }
+}
+
+/// 函数，文件开头与结尾各一块的哈希
+fn partial_hash(path: &Path, size: u64, interrupt: &Interrupt) -> io::Result<u128> {
+    check_interrupt(interrupt)?;
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut block = vec![0; PARTIAL_BLOCK.min(size) as usize];
+    file.read_exact(&mut block)?;
+    hasher.update(&block);
+    if size > PARTIAL_BLOCK {
+        let tail = PARTIAL_BLOCK.min(size - PARTIAL_BLOCK);
+        file.seek(SeekFrom::Start(size - tail))?;
+        block.truncate(tail as usize);
+        file.read_exact(&mut block)?;
+        hasher.update(&block);
+    }
+    Ok(hasher.digest128())
+}
+
+/// 函数，完整内容的哈希，每读取一块检查一次中断
+fn full_hash(path: &Path, interrupt: &Interrupt) -> io::Result<u128> {
+    let mut file = File::open(path)?;
+    let mut hasher = Xxh3::new();
+    let mut buffer = vec![0; 128 * 1024];
+    loop {
+        check_interrupt(interrupt)?;
+        match file.read(&mut buffer)? {
+            0 => return Ok(hasher.digest128()),
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 结构体，扫描时按 glob 排除或包含条目
+#[derive(Debug)]
+pub struct EntryFilter {
+    exclude: GlobSet,
+    /// 为 None 时包含所有文件
+    include: Option<GlobSet>,
+    excluded_count: AtomicU64,
+}
+
+impl EntryFilter {
+    pub fn new(exclude: &[String], include: &[String]) -> Result<Self, globset::Error> {
+        Ok(Self {
+            exclude: build_glob_set(exclude)?,
+            include: match include.is_empty() {
+                true => None,
+                false => Some(build_glob_set(include)?),
+            },
+            excluded_count: AtomicU64::new(0),
+        })
+    }
+
+    /// 判断条目是否被排除，relative 为相对于扫描根目录的路径
+    /// include 只作用于文件，目录总会被进入以查找其中匹配的文件
+    pub fn is_excluded(&self, name: &Path, relative: &Path, is_dir: bool) -> bool {
+        let matches = |set: &GlobSet| set.is_match(name) || set.is_match(relative);
+        let excluded = matches(&self.exclude)
+            || (!is_dir && self.include.as_ref().is_some_and(|set| !matches(set)));
+        if excluded {
+            self.excluded_count.fetch_add(1, Ordering::Relaxed);
+        }
+        excluded
+    }
+
+    /// 被排除的条目数，被排除的目录只计一次
+    pub fn excluded_count(&self) -> u64 {
+        self.excluded_count.load(Ordering::Relaxed)
+    }
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern)?);
+    }
+    builder.build()
+}
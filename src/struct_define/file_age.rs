@@ -0,0 +1,159 @@
+use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime};
+
+use crate::struct_define::file_types::UsageGroup;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// 年龄分布的各区间：名称与上限
+const AGE_BUCKETS: [(&str, Option<u64>); 6] = [
+    ("< 1 day", Some(DAY)),
+    ("< 1 week", Some(7 * DAY)),
+    ("< 1 month", Some(30 * DAY)),
+    ("< 6 months", Some(182 * DAY)),
+    ("< 1 year", Some(365 * DAY)),
+    ("older", None),
+];
+
+/// 枚举，计算文件年龄所用的时间
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AgeTime {
+    /// 最后修改时间
+    Modified,
+    /// 最后访问时间，挂载时使用 noatime 的文件系统上可能不准确
+    Accessed,
+}
+
+impl AgeTime {
+    pub const VARIANTS: [&'static str; 2] = ["modified", "accessed"];
+}
+
+impl FromStr for AgeTime {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "modified" => Ok(AgeTime::Modified),
+            "accessed" => Ok(AgeTime::Accessed),
+            _ => Err(format!("invalid age time: {}", s)),
+        }
+    }
+}
+
+/// 结构体，命令行中的时长，如 `90d`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Age(pub Duration);
+
+impl FromStr for Age {
+    type Err = String;
+
+    /// 单位为 h（小时）、d（天）、w（周）、m（30 天）、y（365 天），省略时为天
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (number, unit) = s.split_at(split);
+        let number = number
+            .parse::<u64>()
+            .map_err(|_| format!("invalid age: {}", s))?;
+        let unit = match unit {
+            "h" => 60 * 60,
+            "" | "d" => DAY,
+            "w" => 7 * DAY,
+            "m" => 30 * DAY,
+            "y" => 365 * DAY,
+            _ => return Err(format!("invalid age unit in {} (use h, d, w, m or y)", s)),
+        };
+        let seconds = number
+            .checked_mul(unit)
+            .ok_or_else(|| format!("age too large: {}", s))?;
+        Ok(Age(Duration::from_secs(seconds)))
+    }
+}
+
+/// 结构体，扫描过程中按年龄筛选文件并统计年龄分布
+#[derive(Debug)]
+pub struct AgeStats {
+    pub time: AgeTime,
+    now: SystemTime,
+    older_than: Option<Duration>,
+    newer_than: Option<Duration>,
+    histogram: Option<[(AtomicU64, AtomicU64); 6]>,
+    filtered_count: AtomicU64,
+}
+
+impl AgeStats {
+    pub fn new(
+        time: AgeTime,
+        older_than: Option<Age>,
+        newer_than: Option<Age>,
+        histogram: bool,
+    ) -> Self {
+        Self {
+            time,
+            now: SystemTime::now(),
+            older_than: older_than.map(|age| age.0),
+            newer_than: newer_than.map(|age| age.0),
+            histogram: histogram.then(Default::default),
+            filtered_count: AtomicU64::new(0),
+        }
+    }
+
+    /// 文件的年龄，时间在未来或无法获取时视为 0
+    pub fn age(&self, modified: Option<SystemTime>, accessed: Option<SystemTime>) -> Duration {
+        let time = match self.time {
+            AgeTime::Modified => modified,
+            AgeTime::Accessed => accessed,
+        };
+        time.and_then(|time| self.now.duration_since(time).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_filtering(&self) -> bool {
+        self.older_than.is_some() || self.newer_than.is_some()
+    }
+
+    /// 判断文件是否满足 --older-than 与 --newer-than，不满足的文件不计入大小
+    pub fn matches(&self, age: Duration) -> bool {
+        let matches = self.older_than.is_none_or(|limit| age >= limit)
+            && self.newer_than.is_none_or(|limit| age < limit);
+        if !matches {
+            self.filtered_count.fetch_add(1, Ordering::Relaxed);
+        }
+        matches
+    }
+
+    /// 将文件计入年龄分布
+    pub fn record(&self, age: Duration, size: u64) {
+        if let Some(histogram) = &self.histogram {
+            let bucket = AGE_BUCKETS
+                .iter()
+                .position(|(_, limit)| limit.is_none_or(|limit| age.as_secs() < limit))
+                .unwrap_or(AGE_BUCKETS.len() - 1);
+            histogram[bucket].0.fetch_add(size, Ordering::Relaxed);
+            histogram[bucket].1.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 由新到旧的年龄分布，未启用 --age-histogram 时为 None
+    pub fn histogram(&self) -> Option<Vec<(&'static str, UsageGroup)>> {
+        let histogram = self.histogram.as_ref()?;
+        Some(
+            AGE_BUCKETS
+                .iter()
+                .zip(histogram)
+                .map(|((name, _), (size, count))| {
+                    let usage = UsageGroup {
+                        size: size.load(Ordering::Relaxed),
+                        count: count.load(Ordering::Relaxed),
+                    };
+                    (*name, usage)
+                })
+                .collect(),
+        )
+    }
+
+    /// 因年龄不满足条件而未计入大小的文件数
+    pub fn filtered_count(&self) -> u64 {
+        self.filtered_count.load(Ordering::Relaxed)
+    }
+}
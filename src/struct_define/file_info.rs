@@ -1,58 +1,115 @@
 use std::error::Error;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 #[cfg(windows)]
 use crate::methods::compressed_size;
 
+#[derive(Debug, Clone)]
 pub enum FileInfo {
-    File { size: u64, volume_id: u64 },
-    Directory { volume_id: u64 },
+    File {
+        apparent_size: u64,
+        allocated_size: u64,
+        volume_id: u64,
+        inode: u64,
+        nlink: u64,
+        /// 所有者的用户与用户组
+        uid: u32,
+        gid: u32,
+        /// 文件系统不支持时为 None
+        modified: Option<SystemTime>,
+        accessed: Option<SystemTime>,
+    },
+    Directory {
+        volume_id: u64,
+        inode: u64,
+        /// 条目增删或改名时更新，用于判断缓存的目录内容是否仍然有效
+        modified: Option<SystemTime>,
+    },
+    Symlink {
+        target: PathBuf,
+        apparent_size: u64,
+        allocated_size: u64,
+    },
 }
 
 impl FileInfo {
+    /// follow_symlinks 为 true 时返回符号链接所指向目标的信息
     #[cfg(windows)]
-    pub fn from_path(path: &Path, apparent: bool) -> Result<Self, Box<dyn Error>> {
+    pub fn from_path(path: &Path, follow_symlinks: bool) -> Result<Self, Box<dyn Error>> {
         use winapi_util::{file, Handle};
         const FILE_ATTRIBUTE_DIRECTORY: u64 = 0x10;
 
+        // Handle::from_path_any 总是打开链接的目标，需先单独判断
+        if !follow_symlinks && path.symlink_metadata()?.file_type().is_symlink() {
+            return Ok(FileInfo::Symlink {
+                target: fs::read_link(path)?,
+                apparent_size: 0,
+                allocated_size: 0,
+            });
+        }
+
         let h = Handle::from_path_any(path)?;
         let md = file::information(h)?;
 
         if md.file_attributes() & FILE_ATTRIBUTE_DIRECTORY != 0 {
             Ok(FileInfo::Directory {
                 volume_id: md.volume_serial_number(),
+                inode: md.file_index(),
+                modified: path.metadata()?.modified().ok(),
             })
         } else {
-            let size = if apparent {
-                compressed_size(path)?
-            } else {
-                md.file_size()
-            };
+            let times = path.metadata()?;
             Ok(FileInfo::File {
-                size,
+                apparent_size: md.file_size(),
+                // 压缩或稀疏文件实际占用的大小
+                allocated_size: compressed_size(path)?,
                 volume_id: md.volume_serial_number(),
+                inode: md.file_index(),
+                nlink: md.number_of_links(),
+                // Windows 没有 uid 与 gid
+                uid: 0,
+                gid: 0,
+                modified: times.modified().ok(),
+                accessed: times.accessed().ok(),
             })
         }
     }
 
+    /// follow_symlinks 为 true 时返回符号链接所指向目标的信息
     #[cfg(unix)]
-    pub fn from_path(path: &Path, apparent: bool) -> Result<Self, Box<dyn Error>> {
+    pub fn from_path(path: &Path, follow_symlinks: bool) -> Result<Self, Box<dyn Error>> {
         use std::os::unix::fs::MetadataExt;
 
-        let md = path.symlink_metadata()?;
+        let md = match follow_symlinks {
+            true => path.metadata()?,
+            false => path.symlink_metadata()?,
+        };
         if md.is_dir() {
             Ok(FileInfo::Directory {
                 volume_id: md.dev(),
+                inode: md.ino(),
+                modified: md.modified().ok(),
+            })
+        } else if md.file_type().is_symlink() {
+            Ok(FileInfo::Symlink {
+                target: fs::read_link(path)?,
+                apparent_size: md.len(),
+                allocated_size: md.blocks() * 512,
             })
         } else {
-            let size = if apparent {
-                md.blocks() * 512
-            } else {
-                md.len()
-            };
             Ok(FileInfo::File {
-                size,
+                apparent_size: md.len(),
+                // st_blocks 的单位固定为 512 字节
+                allocated_size: md.blocks() * 512,
                 volume_id: md.dev(),
+                inode: md.ino(),
+                nlink: md.nlink(),
+                uid: md.uid(),
+                gid: md.gid(),
+                modified: md.modified().ok(),
+                accessed: md.accessed().ok(),
             })
         }
     }
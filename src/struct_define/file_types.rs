@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// 枚举，按类型汇总文件时的分组依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileTypeMode {
+    Off,
+    /// 按扩展名，不区分大小写
+    Extension,
+    /// 按文件开头的字节识别的内容类型
+    Content,
+}
+
+/// 结构体，同一分组中文件的总大小与数量
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UsageGroup {
+    pub size: u64,
+    pub count: u64,
+}
+
+/// 结构体，扫描过程中按类型汇总的文件
+#[derive(Debug)]
+pub struct FileTypeStats {
+    pub mode: FileTypeMode,
+    groups: Mutex<HashMap<String, UsageGroup>>,
+}
+
+/// 文件开头的特征字节：偏移、内容、类型名
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x89PNG\r\n\x1a\n", "PNG image"),
+    (0, b"\xff\xd8\xff", "JPEG image"),
+    (0, b"GIF87a", "GIF image"),
+    (0, b"GIF89a", "GIF image"),
+    (0, b"%PDF-", "PDF document"),
+    (0, b"PK\x03\x04", "Zip archive"),
+    (0, b"\x1f\x8b", "gzip compressed data"),
+    (0, b"BZh", "bzip2 compressed data"),
+    (0, b"\xfd7zXZ\x00", "XZ compressed data"),
+    (0, b"\x28\xb5\x2f\xfd", "Zstandard compressed data"),
+    (0, b"7z\xbc\xaf\x27\x1c", "7-zip archive"),
+    (257, b"ustar", "tar archive"),
+    (0, b"\x7fELF", "ELF executable"),
+    (0, b"MZ", "PE executable"),
+    (4, b"ftyp", "ISO media (MP4, MOV)"),
+    (0, b"\x1a\x45\xdf\xa3", "Matroska video (MKV, WebM)"),
+    (0, b"ID3", "MP3 audio"),
+    (0, b"OggS", "Ogg media"),
+    (0, b"fLaC", "FLAC audio"),
+    (0, b"PAR1", "Parquet data"),
+    (0, b"SQLite format 3\x00", "SQLite database"),
+];
+
+/// 识别类型时读取的字节数，需覆盖 tar 的特征字节
+const SNIFF_LENGTH: usize = 512;
+
+impl FileTypeStats {
+    pub fn new(mode: FileTypeMode) -> Self {
+        Self {
+            mode,
+            groups: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.mode != FileTypeMode::Off
+    }
+
+    /// 将文件计入其类型
+    pub fn add(&self, path: &Path, size: u64) {
+        let key = match self.mode {
+            FileTypeMode::Off => return,
+            FileTypeMode::Extension => extension_of(path),
+            FileTypeMode::Content => sniff_content(path).to_string(),
+        };
+        let mut groups = self.groups.lock().unwrap();
+        let group = groups.entry(key).or_default();
+        group.size += size;
+        group.count += 1;
+    }
+
+    /// 按总大小降序排列的类型
+    pub fn sorted(&self) -> Vec<(String, UsageGroup)> {
+        let groups = self.groups.lock().unwrap();
+        let mut sorted = groups
+            .iter()
+            .map(|(key, group)| (key.clone(), *group))
+            .collect::<Vec<_>>();
+        sorted.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(&b.0)));
+        sorted
+    }
+}
+
+/// 函数，小写的扩展名，如 `.log`
+fn extension_of(path: &Path) -> String {
+    match path.extension() {
+        Some(extension) => format!(".{}", extension.to_string_lossy().to_lowercase()),
+        None => "(no extension)".to_string(),
+    }
+}
+
+/// 函数，根据文件开头的字节识别类型，类似 file(1)
+fn sniff_content(path: &Path) -> &'static str {
+    // 打开 FIFO 或设备会阻塞或产生副作用，只读取普通文件
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => {}
+        Ok(_) => return "special file",
+        Err(_) => return "unreadable",
+    }
+    let mut head = Vec::with_capacity(SNIFF_LENGTH);
+    let read =
+        File::open(path).and_then(|file| file.take(SNIFF_LENGTH as u64).read_to_end(&mut head));
+    if read.is_err() {
+        return "unreadable";
+    }
+    if head.is_empty() {
+        return "empty";
+    }
+    let signature = SIGNATURES
+        .iter()
+        .find(|(offset, magic, _)| head.get(*offset..offset + magic.len()) == Some(magic));
+    if let Some((_, _, name)) = signature {
+        return name;
+    }
+    // 截断处可能落在多字节字符中间
+    let text = match std::str::from_utf8(&head) {
+        Ok(text) => Some(text),
+        Err(error) if error.error_len().is_none() => {
+            std::str::from_utf8(&head[..error.valid_up_to()]).ok()
+        }
+        Err(_) => None,
+    };
+    match text {
+        Some(text) if !text.contains(|c: char| c.is_control() && !c.is_whitespace()) => "text",
+        _ => "data",
+    }
+}
@@ -0,0 +1,41 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// 结构体，记录并行扫描过程中已计数过的硬链接 (dev, ino)
+#[derive(Debug, Default)]
+pub struct HardLinkTracker {
+    seen: Mutex<HashSet<(u64, u64)>>,
+    duplicate_count: AtomicU64,
+    duplicate_size: AtomicU64,
+}
+
+impl HardLinkTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 判断该文件是否为已计数 inode 的另一个硬链接，是则记录其大小
+    pub fn is_duplicate(&self, volume_id: u64, inode: u64, nlink: u64, size: u64) -> bool {
+        // 只有一个链接的文件不可能重复，无需加锁
+        if nlink <= 1 {
+            return false;
+        }
+        let first_seen = self.seen.lock().unwrap().insert((volume_id, inode));
+        if !first_seen {
+            self.duplicate_count.fetch_add(1, Ordering::Relaxed);
+            self.duplicate_size.fetch_add(size, Ordering::Relaxed);
+        }
+        !first_seen
+    }
+
+    /// 被去重的硬链接数量
+    pub fn duplicate_count(&self) -> u64 {
+        self.duplicate_count.load(Ordering::Relaxed)
+    }
+
+    /// 被去重的硬链接所占字节数
+    pub fn duplicate_size(&self) -> u64 {
+        self.duplicate_size.load(Ordering::Relaxed)
+    }
+}
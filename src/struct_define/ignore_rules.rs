@@ -0,0 +1,121 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// 枚举，.gitignore 等忽略规则的使用方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoreMode {
+    /// 不读取忽略规则
+    Off,
+    /// 跳过被忽略的条目
+    Respect,
+    /// 只统计被忽略的条目
+    OnlyIgnored,
+}
+
+/// 结构体，扫描时使用的忽略规则
+#[derive(Debug)]
+pub struct IgnoreRules {
+    pub mode: IgnoreMode,
+    /// 扫描根目录之外、直到仓库根目录的规则，由近及远
+    outer: Vec<Gitignore>,
+    /// 扫描根目录及其绝对路径，外层规则以绝对路径匹配
+    root: PathBuf,
+    absolute_root: PathBuf,
+    skipped_count: AtomicU64,
+}
+
+impl IgnoreRules {
+    pub fn new(mode: IgnoreMode, root: &Path) -> Self {
+        let absolute_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let outer = match mode {
+            IgnoreMode::Off => Vec::new(),
+            _ => load_outer_rules(&absolute_root),
+        };
+        Self {
+            mode,
+            outer,
+            root: root.to_path_buf(),
+            absolute_root,
+            skipped_count: AtomicU64::new(0),
+        }
+    }
+
+    /// 判断路径是否被忽略，inner 为扫描根目录之内的规则，由近及远
+    pub fn is_ignored<'a>(
+        &self,
+        inner: impl Iterator<Item = &'a Gitignore>,
+        path: &Path,
+        is_dir: bool,
+    ) -> bool {
+        // 越靠近条目的规则优先级越高，第一个有结论的规则即为结果
+        if let Some(ignored) = first_match(inner, path, is_dir) {
+            return ignored;
+        }
+        if self.outer.is_empty() {
+            return false;
+        }
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let absolute = self.absolute_root.join(relative);
+        first_match(self.outer.iter(), &absolute, is_dir).unwrap_or(false)
+    }
+
+    pub fn add_skipped(&self) {
+        self.skipped_count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 因忽略规则而被跳过的条目数
+    pub fn skipped_count(&self) -> u64 {
+        self.skipped_count.load(Ordering::Relaxed)
+    }
+}
+
+/// 函数，依次匹配规则，返回第一个忽略或白名单的结论
+fn first_match<'a>(
+    rules: impl Iterator<Item = &'a Gitignore>,
+    path: &Path,
+    is_dir: bool,
+) -> Option<bool> {
+    rules
+        .map(|rules| rules.matched(path, is_dir))
+        .find(|matched| !matched.is_none())
+        .map(|matched| matched.is_ignore())
+}
+
+/// 函数，读取 dir 下的 .git/info/exclude、.gitignore 与 .ignore，后者优先级更高
+pub fn load_dir_rules(dir: &Path) -> Option<Gitignore> {
+    let mut builder = GitignoreBuilder::new(dir);
+    let mut found = false;
+    for file in [
+        dir.join(".git").join("info").join("exclude"),
+        dir.join(".gitignore"),
+        dir.join(".ignore"),
+    ] {
+        if file.is_file() {
+            // 与 git 一致，忽略无法解析的行
+            let _ = builder.add(file);
+            found = true;
+        }
+    }
+    match found {
+        true => builder.build().ok(),
+        false => None,
+    }
+}
+
+/// 函数，从扫描根目录的父目录向上读取规则，直到包含 .git 的仓库根目录
+fn load_outer_rules(root: &Path) -> Vec<Gitignore> {
+    let mut rules = Vec::new();
+    // 扫描根目录本身就是仓库根目录时无需向上查找
+    if root.join(".git").exists() {
+        return rules;
+    }
+    for dir in root.ancestors().skip(1) {
+        rules.extend(load_dir_rules(dir));
+        if dir.join(".git").exists() {
+            return rules;
+        }
+    }
+    // 不在任何仓库中
+    Vec::new()
+}
@@ -0,0 +1,49 @@
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// 被 Ctrl-C 终止时的退出码，与 shell 中 128 + SIGINT 一致
+const EXIT_INTERRUPTED: i32 = 130;
+
+/// 结构体，通知扫描尽快停止，已读取的部分仍会被输出
+#[derive(Debug, Clone, Default)]
+pub struct Interrupt {
+    triggered: Arc<AtomicBool>,
+    /// 正在执行能够响应中断的操作，如扫描与比较文件内容
+    active: Arc<AtomicBool>,
+}
+
+impl Interrupt {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 在 catch 中第一次 Ctrl-C 只设置标志，第二次立即退出；其他时候 Ctrl-C 立即退出。
+    /// 每个进程只能调用一次
+    pub fn install_handler(&self) -> Result<(), ctrlc::Error> {
+        let interrupt = self.clone();
+        ctrlc::set_handler(move || {
+            if !interrupt.active.load(Ordering::SeqCst)
+                || interrupt.triggered.swap(true, Ordering::SeqCst)
+            {
+                process::exit(EXIT_INTERRUPTED);
+            }
+        })
+    }
+
+    /// 执行 f，其间的 Ctrl-C 只设置标志，由 f 检查后尽快返回
+    pub fn catch<T>(&self, f: impl FnOnce() -> T) -> T {
+        let previous = self.active.swap(true, Ordering::SeqCst);
+        let result = f();
+        self.active.store(previous, Ordering::SeqCst);
+        result
+    }
+
+    pub fn trigger(&self) {
+        self.triggered.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        self.triggered.load(Ordering::Relaxed)
+    }
+}
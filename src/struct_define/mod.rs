@@ -1,7 +1,26 @@
+pub mod analysis_context;
 pub mod analysis_item;
+pub mod compact_tree;
 pub mod config;
+pub mod dir_handle;
 pub mod display_info;
+pub mod duplicates;
+pub mod entry_filter;
+pub mod file_age;
 pub mod file_info;
+pub mod file_types;
+pub mod hard_link;
+pub mod ignore_rules;
+pub mod interrupt;
+pub mod mount_point;
+pub mod owners;
+pub mod progress;
+pub mod scan_cache;
+pub mod scan_error;
+pub mod size_diff;
+pub mod symbolic_link;
+pub mod top_entries;
+pub mod visited_dirs;
 
 // 模块，终端输出树形结构视觉效果
 pub mod tree_shape {
@@ -0,0 +1,70 @@
+use std::path::Path;
+
+/// 结构体，扫描中遇到的挂载点（与父目录不在同一文件系统）
+#[derive(Debug, Clone)]
+pub struct MountPoint {
+    /// 挂载的设备，如 `/dev/sdb1`
+    pub source: String,
+    /// 是否进入该文件系统进行了扫描
+    pub scanned: bool,
+}
+
+impl MountPoint {
+    pub fn new(path: &Path, volume_id: u64, scanned: bool) -> Self {
+        Self {
+            source: mount_source(path).unwrap_or_else(|| format!("device {}", volume_id)),
+            scanned,
+        }
+    }
+}
+
+/// 函数，从 /proc/self/mounts 查找挂载到 path 上的设备
+#[cfg(target_os = "linux")]
+fn mount_source(path: &Path) -> Option<String> {
+    let path = path.canonicalize().ok()?;
+    let mounts = std::fs::read_to_string("/proc/self/mounts").ok()?;
+    // 同一挂载点可能出现多次，最后一条才是当前可见的挂载
+    mounts
+        .lines()
+        .rev()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let source = fields.next()?;
+            let target = fields.next()?;
+            Some((unescape_mount_field(source), unescape_mount_field(target)))
+        })
+        .filter(|(_, target)| Path::new(target) == path)
+        .map(|(source, _)| source)
+        .next()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn mount_source(_path: &Path) -> Option<String> {
+    None
+}
+
+/// 函数，还原 /proc/self/mounts 中以八进制转义的空白字符，如 `\040`
+#[cfg(target_os = "linux")]
+fn unescape_mount_field(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes.get(i + 1..i + 4).and_then(|digits| {
+            std::str::from_utf8(digits)
+                .ok()
+                .and_then(|digits| u8::from_str_radix(digits, 8).ok())
+        });
+        match (bytes[i], escaped) {
+            (b'\\', Some(byte)) => {
+                unescaped.push(byte);
+                i += 4;
+            }
+            (byte, _) => {
+                unescaped.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::struct_define::file_types::UsageGroup;
+
+/// 枚举，按文件的所有者还是用户组汇总
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OwnerKind {
+    User,
+    Group,
+}
+
+impl OwnerKind {
+    /// 记录名称的文件，每行以 `name:password:id:` 开头
+    fn database(self) -> &'static str {
+        match self {
+            OwnerKind::User => "/etc/passwd",
+            OwnerKind::Group => "/etc/group",
+        }
+    }
+}
+
+/// 结构体，扫描过程中按 uid 或 gid 汇总的文件，可同时按顶层目录分别汇总
+#[derive(Debug)]
+pub struct OwnerStats {
+    pub kind: OwnerKind,
+    enabled: bool,
+    per_directory: bool,
+    totals: Mutex<HashMap<u32, UsageGroup>>,
+    directories: Mutex<HashMap<OsString, HashMap<u32, UsageGroup>>>,
+}
+
+impl OwnerStats {
+    pub fn new(kind: OwnerKind, enabled: bool, per_directory: bool) -> Self {
+        Self {
+            kind,
+            enabled,
+            per_directory,
+            totals: Mutex::new(HashMap::new()),
+            directories: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn per_directory(&self) -> bool {
+        self.enabled && self.per_directory
+    }
+
+    /// 将 root 下的文件计入其所有者，直接位于 root 中的文件归入 `.`
+    pub fn add(&self, id: u32, size: u64, path: &Path, root: &Path) {
+        if !self.enabled {
+            return;
+        }
+        add_usage(&mut self.totals.lock().unwrap(), id, size);
+        if self.per_directory {
+            let top_level = top_level_dir(path, root).unwrap_or(OsStr::new("."));
+            let mut directories = self.directories.lock().unwrap();
+            let usage = directories.entry(top_level.to_os_string()).or_default();
+            add_usage(usage, id, size);
+        }
+    }
+
+    /// 按总大小降序排列的所有者
+    pub fn totals(&self) -> Vec<(String, UsageGroup)> {
+        let names = read_names(self.kind);
+        sorted_by_size(&self.totals.lock().unwrap(), &names)
+    }
+
+    /// 按总大小降序排列的顶层目录，及其中按总大小降序排列的所有者
+    pub fn directories(&self) -> Vec<(OsString, Vec<(String, UsageGroup)>)> {
+        let names = read_names(self.kind);
+        let directories = self.directories.lock().unwrap();
+        let mut sorted = directories
+            .iter()
+            .map(|(dir, usage)| (dir.clone(), sorted_by_size(usage, &names)))
+            .collect::<Vec<_>>();
+        let total = |owners: &[(String, UsageGroup)]| -> u64 {
+            owners.iter().map(|(_, usage)| usage.size).sum()
+        };
+        sorted.sort_by(|a, b| total(&b.1).cmp(&total(&a.1)).then_with(|| a.0.cmp(&b.0)));
+        sorted
+    }
+}
+
+fn add_usage(usage: &mut HashMap<u32, UsageGroup>, id: u32, size: u64) {
+    let group = usage.entry(id).or_default();
+    group.size += size;
+    group.count += 1;
+}
+
+fn sorted_by_size(
+    usage: &HashMap<u32, UsageGroup>,
+    names: &HashMap<u32, String>,
+) -> Vec<(String, UsageGroup)> {
+    let mut sorted = usage
+        .iter()
+        .map(|(id, group)| {
+            // 没有名称的 id 直接显示数字
+            let name = names.get(id).cloned().unwrap_or_else(|| id.to_string());
+            (name, *group)
+        })
+        .collect::<Vec<_>>();
+    sorted.sort_by(|a, b| b.1.size.cmp(&a.1.size).then_with(|| a.0.cmp(&b.0)));
+    sorted
+}
+
+/// 函数，path 位于 root 下的第一级目录，直接位于 root 中时返回 None
+fn top_level_dir<'a>(path: &'a Path, root: &Path) -> Option<&'a OsStr> {
+    let mut components = path.strip_prefix(root).ok()?.components();
+    let first = components.next()?;
+    components.next().map(|_| first.as_os_str())
+}
+
+/// 函数，从 /etc/passwd 或 /etc/group 读取 id 对应的名称
+fn read_names(kind: OwnerKind) -> HashMap<u32, String> {
+    let content = fs::read_to_string(kind.database()).unwrap_or_default();
+    let mut names = HashMap::new();
+    for line in content.lines().filter(|line| !line.starts_with('#')) {
+        let mut fields = line.split(':');
+        if let (Some(name), Some(id)) = (fields.next(), fields.nth(1)) {
+            if let Ok(id) = id.parse() {
+                // 同一 id 有多个名称时取第一个，与 getpwuid 一致
+                names.entry(id).or_insert_with(|| name.to_string());
+            }
+        }
+    }
+    names
+}
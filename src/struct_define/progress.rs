@@ -0,0 +1,117 @@
+use crossterm::cursor::MoveToColumn;
+use crossterm::queue;
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::methods::convert_to_bytes;
+
+/// 刷新进度行的间隔
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 结构体，扫描过程中由各并行任务更新的进度
+#[derive(Debug, Default)]
+pub struct ScanProgress {
+    files: AtomicU64,
+    dirs: AtomicU64,
+    bytes: AtomicU64,
+    errors: AtomicU64,
+    /// 最近开始读取的目录
+    current: Mutex<PathBuf>,
+}
+
+impl ScanProgress {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 开始读取目录
+    pub fn visit_dir(&self, path: &Path) {
+        self.dirs.fetch_add(1, Ordering::Relaxed);
+        // 进度行只需大致反映当前位置，锁被占用时直接跳过
+        if let Ok(mut current) = self.current.try_lock() {
+            current.clear();
+            current.push(path);
+        }
+    }
+
+    /// 访问一个文件或符号链接，size 为计入结果的大小
+    pub fn visit_file(&self, size: u64) {
+        self.files.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(size, Ordering::Relaxed);
+    }
+
+    pub fn add_errors(&self, count: usize) {
+        self.errors.fetch_add(count as u64, Ordering::Relaxed);
+    }
+
+    pub fn files(&self) -> u64 {
+        self.files.load(Ordering::Relaxed)
+    }
+
+    pub fn dirs(&self) -> u64 {
+        self.dirs.load(Ordering::Relaxed)
+    }
+
+    pub fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    pub fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    /// 不超过 width 个字符的进度行，当前路径过长时省略开头
+    pub fn line(&self, width: usize) -> String {
+        let mut line = format!(
+            "Scanning: {} files, {} dirs, {}",
+            self.files(),
+            self.dirs(),
+            convert_to_bytes(self.bytes() as f64)
+        );
+        if self.errors() > 0 {
+            line.push_str(&format!(", {} errors", self.errors()));
+        }
+        let current = self.current.lock().unwrap().display().to_string();
+        let room = width.saturating_sub(line.chars().count() + 2);
+        let length = current.chars().count();
+        if length <= room {
+            line.push_str(&format!(": {}", current));
+        } else if room > 3 {
+            let tail = current
+                .chars()
+                .skip(length - (room - 3))
+                .collect::<String>();
+            line.push_str(&format!(": ...{}", tail));
+        }
+        line.chars().take(width).collect()
+    }
+
+    /// 在 stderr 上定时刷新进度行，直到 stop 收到消息或被关闭，最后清除进度行
+    pub fn report(&self, stop: Receiver<()>) -> io::Result<()> {
+        while let Err(RecvTimeoutError::Timeout) = stop.recv_timeout(REFRESH_INTERVAL) {
+            // 留出最后一列，避免部分终端自动换行
+            let width = match terminal::size() {
+                Ok((columns, _)) if columns > 0 => columns as usize,
+                _ => 80,
+            };
+            // 只在绘制时持有 stderr 的锁，其他线程（如 panic 信息）仍能写入
+            let mut stderr = io::stderr().lock();
+            queue!(
+                stderr,
+                MoveToColumn(0),
+                Clear(ClearType::CurrentLine),
+                Print(self.line(width.saturating_sub(1)))
+            )?;
+            stderr.flush()?;
+        }
+        let mut stderr = io::stderr().lock();
+        queue!(stderr, MoveToColumn(0), Clear(ClearType::CurrentLine))?;
+        stderr.flush()
+    }
+}
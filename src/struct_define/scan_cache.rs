@@ -0,0 +1,358 @@
+//! `--cache` 的文件格式，整数均为小端序，字节串以长度开头：
+//!
+//! ```txt
+//! "MRDUCACHE" version:u32 count:u64 <dir>*
+//!
+//! <dir>   = path:bytes32 volume_id:u64 inode:u64 modified:<time> count:u32 <entry>*
+//! <entry> = name:bytes16 kind:u8 info:u8 [<info>]      // kind: 0 未知 1 目录 2 符号链接 3 其他
+//! <info>  = 1 apparent:u64 allocated:u64 volume_id:u64 inode:u64 nlink:u64 uid:u32 gid:u32
+//!             modified:<time>? accessed:<time>?        // 文件
+//!         | 2 target:bytes32 apparent:u64 allocated:u64  // 符号链接
+//! <time>  = secs:i64 nanos:u32                          // 相对 1970 年，末尾带 ? 的前面另有 1 字节表示是否存在
+//! ```
+//!
+//! 目录的修改时间只在其中的条目增删或改名时变化，文件内容被原地修改时不会变化，
+//! 因此复用的文件大小可能过时，需要时使用 `--verify` 完整地重新扫描。
+
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::struct_define::dir_handle::{ChildEntry, EntryKind};
+use crate::struct_define::file_info::FileInfo;
+
+const MAGIC: &[u8] = b"MRDUCACHE";
+const CACHE_VERSION: u32 = 1;
+
+/// 结构体，上次扫描时读取的一个目录
+#[derive(Debug)]
+struct CachedDir {
+    volume_id: u64,
+    inode: u64,
+    modified: SystemTime,
+    entries: Vec<ChildEntry>,
+}
+
+/// 结构体，保存在磁盘上的目录内容，修改时间未变的目录无需再读取
+#[derive(Debug)]
+pub struct ScanCache {
+    file: PathBuf,
+    previous: Mutex<HashMap<PathBuf, CachedDir>>,
+    current: Mutex<Vec<(PathBuf, CachedDir)>>,
+    reused: AtomicU64,
+}
+
+impl ScanCache {
+    /// 读取缓存文件，文件不存在、版本不同、内容损坏或 verify 为 true 时从空缓存开始
+    pub fn load(file: &Path, verify: bool) -> Self {
+        let previous = match verify {
+            true => HashMap::new(),
+            false => File::open(file)
+                .and_then(|file| read_cache(&mut BufReader::new(file)))
+                .unwrap_or_default(),
+        };
+        Self {
+            file: file.to_path_buf(),
+            previous: Mutex::new(previous),
+            current: Mutex::new(Vec::new()),
+            reused: AtomicU64::new(0),
+        }
+    }
+
+    /// 目录自上次扫描后未变化时取出其条目
+    pub fn reuse(
+        &self,
+        path: &Path,
+        volume_id: u64,
+        inode: u64,
+        modified: Option<SystemTime>,
+    ) -> Option<Vec<ChildEntry>> {
+        let cached = self.previous.lock().unwrap().remove(path)?;
+        let unchanged = cached.volume_id == volume_id
+            && cached.inode == inode
+            && Some(cached.modified) == modified;
+        if !unchanged {
+            return None;
+        }
+        self.reused.fetch_add(1, Ordering::Relaxed);
+        Some(cached.entries)
+    }
+
+    /// 记录完整读取的目录，写入新的缓存
+    pub fn record(
+        &self,
+        path: PathBuf,
+        volume_id: u64,
+        inode: u64,
+        modified: SystemTime,
+        entries: Vec<ChildEntry>,
+    ) {
+        let dir = CachedDir {
+            volume_id,
+            inode,
+            modified,
+            entries,
+        };
+        self.current.lock().unwrap().push((path, dir));
+    }
+
+    /// 内容来自缓存的目录数
+    pub fn reused_count(&self) -> u64 {
+        self.reused.load(Ordering::Relaxed)
+    }
+
+    pub fn file(&self) -> &Path {
+        &self.file
+    }
+
+    /// 以本次扫描记录的目录替换缓存文件
+    pub fn save(&self) -> io::Result<()> {
+        let mut temporary = self.file.clone().into_os_string();
+        temporary.push(".tmp");
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        write_cache(&self.current.lock().unwrap(), &mut writer)?;
+        writer.into_inner()?.sync_all()?;
+        // 写入完成后再替换，中途退出不会留下损坏的缓存
+        fs::rename(&temporary, &self.file)
+    }
+}
+
+fn write_cache<W: Write>(dirs: &[(PathBuf, CachedDir)], writer: &mut W) -> io::Result<()> {
+    writer.write_all(MAGIC)?;
+    writer.write_all(&CACHE_VERSION.to_le_bytes())?;
+    writer.write_all(&(dirs.len() as u64).to_le_bytes())?;
+    for (path, dir) in dirs {
+        write_bytes32(writer, path.as_os_str().as_encoded_bytes())?;
+        writer.write_all(&dir.volume_id.to_le_bytes())?;
+        writer.write_all(&dir.inode.to_le_bytes())?;
+        write_time(writer, dir.modified)?;
+        writer.write_all(&(dir.entries.len() as u32).to_le_bytes())?;
+        for entry in &dir.entries {
+            write_entry(writer, entry)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_entry<W: Write>(writer: &mut W, entry: &ChildEntry) -> io::Result<()> {
+    let name = entry.name.as_encoded_bytes();
+    writer.write_all(&(name.len() as u16).to_le_bytes())?;
+    writer.write_all(name)?;
+    let kind = match entry.kind {
+        None => 0u8,
+        Some(EntryKind::Dir) => 1,
+        Some(EntryKind::Symlink) => 2,
+        Some(EntryKind::Other) => 3,
+    };
+    writer.write_all(&[kind])?;
+    match &entry.info {
+        Some(FileInfo::File {
+            apparent_size,
+            allocated_size,
+            volume_id,
+            inode,
+            nlink,
+            uid,
+            gid,
+            modified,
+            accessed,
+        }) => {
+            writer.write_all(&[1])?;
+            for value in [apparent_size, allocated_size, volume_id, inode, nlink] {
+                writer.write_all(&value.to_le_bytes())?;
+            }
+            writer.write_all(&uid.to_le_bytes())?;
+            writer.write_all(&gid.to_le_bytes())?;
+            for time in [modified, accessed] {
+                writer.write_all(&[time.is_some() as u8])?;
+                if let Some(time) = time {
+                    write_time(writer, *time)?;
+                }
+            }
+        }
+        Some(FileInfo::Symlink {
+            target,
+            apparent_size,
+            allocated_size,
+        }) => {
+            writer.write_all(&[2])?;
+            write_bytes32(writer, target.as_os_str().as_encoded_bytes())?;
+            writer.write_all(&apparent_size.to_le_bytes())?;
+            writer.write_all(&allocated_size.to_le_bytes())?;
+        }
+        // 目录总会重新读取
+        Some(FileInfo::Directory { .. }) | None => writer.write_all(&[0])?,
+    }
+    Ok(())
+}
+
+fn write_bytes32<W: Write>(writer: &mut W, bytes: &[u8]) -> io::Result<()> {
+    writer.write_all(&(bytes.len() as u32).to_le_bytes())?;
+    writer.write_all(bytes)
+}
+
+fn write_time<W: Write>(writer: &mut W, time: SystemTime) -> io::Result<()> {
+    let (secs, nanos) = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => (since.as_secs() as i64, since.subsec_nanos()),
+        Err(error) => {
+            // 1970 年以前：秒数向下取整，纳秒保持为正
+            let before = error.duration();
+            match before.subsec_nanos() {
+                0 => (-(before.as_secs() as i64), 0),
+                nanos => (-(before.as_secs() as i64) - 1, 1_000_000_000 - nanos),
+            }
+        }
+    };
+    writer.write_all(&secs.to_le_bytes())?;
+    writer.write_all(&nanos.to_le_bytes())
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_cache<R: Read>(reader: &mut R) -> io::Result<HashMap<PathBuf, CachedDir>> {
+    let mut magic = [0; MAGIC.len()];
+    reader.read_exact(&mut magic)?;
+    if magic != MAGIC || read_u32(reader)? != CACHE_VERSION {
+        return Err(invalid("not a cache file of this version"));
+    }
+    let count = read_u64(reader)?;
+    let mut dirs = HashMap::new();
+    for _ in 0..count {
+        let path = PathBuf::from(read_os_string(reader, 4)?);
+        let volume_id = read_u64(reader)?;
+        let inode = read_u64(reader)?;
+        let modified = read_time(reader)?;
+        let entry_count = read_u32(reader)?;
+        let entries = (0..entry_count)
+            .map(|_| read_entry(reader))
+            .collect::<io::Result<Vec<_>>>()?;
+        let dir = CachedDir {
+            volume_id,
+            inode,
+            modified,
+            entries,
+        };
+        dirs.insert(path, dir);
+    }
+    Ok(dirs)
+}
+
+fn read_entry<R: Read>(reader: &mut R) -> io::Result<ChildEntry> {
+    let name = read_os_string(reader, 2)?;
+    let kind = match read_u8(reader)? {
+        0 => None,
+        1 => Some(EntryKind::Dir),
+        2 => Some(EntryKind::Symlink),
+        3 => Some(EntryKind::Other),
+        _ => return Err(invalid("unknown entry kind")),
+    };
+    let info = match read_u8(reader)? {
+        0 => None,
+        1 => {
+            let apparent_size = read_u64(reader)?;
+            let allocated_size = read_u64(reader)?;
+            let volume_id = read_u64(reader)?;
+            let inode = read_u64(reader)?;
+            let nlink = read_u64(reader)?;
+            let uid = read_u32(reader)?;
+            let gid = read_u32(reader)?;
+            let modified = read_optional_time(reader)?;
+            let accessed = read_optional_time(reader)?;
+            Some(FileInfo::File {
+                apparent_size,
+                allocated_size,
+                volume_id,
+                inode,
+                nlink,
+                uid,
+                gid,
+                modified,
+                accessed,
+            })
+        }
+        2 => Some(FileInfo::Symlink {
+            target: PathBuf::from(read_os_string(reader, 4)?),
+            apparent_size: read_u64(reader)?,
+            allocated_size: read_u64(reader)?,
+        }),
+        _ => return Err(invalid("unknown entry info")),
+    };
+    Ok(ChildEntry { name, kind, info })
+}
+
+fn read_u8<R: Read>(reader: &mut R) -> io::Result<u8> {
+    let mut bytes = [0; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// 以 2 或 4 字节长度开头的字节串
+fn read_os_string<R: Read>(reader: &mut R, length_bytes: usize) -> io::Result<OsString> {
+    let length = match length_bytes {
+        2 => {
+            let mut bytes = [0; 2];
+            reader.read_exact(&mut bytes)?;
+            u16::from_le_bytes(bytes) as usize
+        }
+        _ => read_u32(reader)? as usize,
+    };
+    // 按实际读到的字节分配，损坏的长度不会导致过大的分配
+    let mut bytes = Vec::new();
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+    if bytes.len() != length {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    os_string_from_bytes(bytes)
+}
+
+#[cfg(unix)]
+fn os_string_from_bytes(bytes: Vec<u8>) -> io::Result<OsString> {
+    use std::os::unix::ffi::OsStringExt;
+    Ok(OsString::from_vec(bytes))
+}
+
+/// 其他平台上只接受 UTF-8，含有其他字节的缓存被整体丢弃
+#[cfg(not(unix))]
+fn os_string_from_bytes(bytes: Vec<u8>) -> io::Result<OsString> {
+    String::from_utf8(bytes)
+        .map(OsString::from)
+        .map_err(|_| invalid("file name is not valid UTF-8"))
+}
+
+fn read_time<R: Read>(reader: &mut R) -> io::Result<SystemTime> {
+    let secs = read_u64(reader)? as i64;
+    let nanos = Duration::from_nanos(read_u32(reader)? as u64);
+    let time = match secs >= 0 {
+        true => UNIX_EPOCH.checked_add(Duration::from_secs(secs as u64) + nanos),
+        false => UNIX_EPOCH
+            .checked_sub(Duration::from_secs(secs.unsigned_abs()))
+            .and_then(|time| time.checked_add(nanos)),
+    };
+    time.ok_or_else(|| invalid("time out of range"))
+}
+
+fn read_optional_time<R: Read>(reader: &mut R) -> io::Result<Option<SystemTime>> {
+    match read_u8(reader)? {
+        0 => Ok(None),
+        _ => read_time(reader).map(Some),
+    }
+}
@@ -0,0 +1,40 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 结构体，扫描时无法读取而被跳过的条目
+#[derive(Debug, Clone)]
+pub struct ScanError {
+    pub path: PathBuf,
+    pub kind: io::ErrorKind,
+    pub message: String,
+}
+
+impl ScanError {
+    pub fn new(path: &Path, error: &io::Error) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            kind: error.kind(),
+            message: error.to_string(),
+        }
+    }
+
+    /// 从 `analyze` 返回的错误中提取 I/O 错误，其余错误记为 `ErrorKind::Other`
+    pub fn from_boxed(path: &Path, error: &(dyn Error + 'static)) -> Self {
+        match error.downcast_ref::<io::Error>() {
+            Some(error) => Self::new(path, error),
+            None => Self {
+                path: path.to_path_buf(),
+                kind: io::ErrorKind::Other,
+                message: error.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.message)
+    }
+}
@@ -0,0 +1,113 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+
+use crate::struct_define::analysis_item::AnalysisItem;
+use crate::struct_define::config::SizeMode;
+
+/// 枚举，条目在两次扫描之间的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    Added,
+    Removed,
+    Grown,
+    Shrunk,
+    Unchanged,
+}
+
+impl Change {
+    pub fn label(self) -> &'static str {
+        match self {
+            Change::Added => "added",
+            Change::Removed => "removed",
+            Change::Grown => "grown",
+            Change::Shrunk => "shrunk",
+            Change::Unchanged => "unchanged",
+        }
+    }
+}
+
+/// 结构体，按名称对齐的两棵分析树的大小变化
+pub struct SizeDiff {
+    pub name: OsString,
+    /// 不存在的一侧大小为 0
+    pub old_size: u64,
+    pub new_size: u64,
+    pub change: Change,
+    /// 按变化量的绝对值降序排列
+    pub children: Vec<SizeDiff>,
+}
+
+impl SizeDiff {
+    /// 比较同一位置的新旧条目，至少有一侧存在
+    pub fn compare(
+        old: Option<&AnalysisItem>,
+        new: Option<&AnalysisItem>,
+        size_mode: SizeMode,
+    ) -> Self {
+        let name = match (old, new) {
+            (_, Some(item)) | (Some(item), None) => item.name.clone(),
+            (None, None) => OsString::new(),
+        };
+        let old_size = old.map_or(0, |item| item.size(size_mode));
+        let new_size = new.map_or(0, |item| item.size(size_mode));
+        let change = match (old, new) {
+            (None, _) => Change::Added,
+            (_, None) => Change::Removed,
+            _ if new_size > old_size => Change::Grown,
+            _ if new_size < old_size => Change::Shrunk,
+            _ => Change::Unchanged,
+        };
+
+        let old_children = old.and_then(|item| item.children.as_ref());
+        let new_children = new.and_then(|item| item.children.as_ref());
+        let mut unmatched = old_children
+            .into_iter()
+            .flatten()
+            .map(|child| (child.name.as_os_str(), child))
+            .collect::<HashMap<&OsStr, &AnalysisItem>>();
+        let mut children = new_children
+            .into_iter()
+            .flatten()
+            .map(|child| {
+                let old_child = unmatched.remove(child.name.as_os_str());
+                SizeDiff::compare(old_child, Some(child), size_mode)
+            })
+            .collect::<Vec<_>>();
+        // 只存在于旧树中的条目
+        children.extend(
+            old_children
+                .into_iter()
+                .flatten()
+                .filter(|child| unmatched.contains_key(child.name.as_os_str()))
+                .map(|child| SizeDiff::compare(Some(child), None, size_mode)),
+        );
+        children.sort_by_key(|child| Reverse(child.delta().unsigned_abs()));
+
+        Self {
+            name,
+            old_size,
+            new_size,
+            change,
+            children,
+        }
+    }
+
+    /// 大小的变化量，增长为正
+    pub fn delta(&self) -> i64 {
+        self.new_size as i64 - self.old_size as i64
+    }
+
+    /// 各子项变化量的绝对值之和，大小不变的目录中也可能有增有减
+    pub fn churn(&self) -> u64 {
+        self.children
+            .iter()
+            .map(|child| child.delta().unsigned_abs())
+            .sum()
+    }
+
+    /// 子树中是否有条目新增、删除或改变大小，大小不变的移动也算作变化
+    pub fn is_changed(&self) -> bool {
+        self.change != Change::Unchanged || self.children.iter().any(SizeDiff::is_changed)
+    }
+}
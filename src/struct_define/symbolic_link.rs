@@ -0,0 +1,14 @@
+use std::path::PathBuf;
+
+/// 结构体，扫描中遇到的符号链接
+#[derive(Debug, Clone)]
+pub struct SymbolicLink {
+    /// 链接指向的路径，即 readlink 的结果
+    pub target: PathBuf,
+    /// 是否跟随链接分析了其目标
+    pub followed: bool,
+    /// 目标目录是其自身的祖先目录，为避免死循环未被跟随
+    pub cycle: bool,
+    /// 目标目录已通过其他路径计入大小，未再次跟随
+    pub duplicate: bool,
+}
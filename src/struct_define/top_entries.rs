@@ -0,0 +1,57 @@
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// 预先分配的条目数上限，更大的 N 在扫描中按需增长
+const PREALLOCATED: usize = 1024;
+
+/// 结构体，扫描过程中保留最大的 N 个条目，堆顶为其中最小的一个
+#[derive(Debug)]
+pub struct TopEntries {
+    limit: usize,
+    heap: Mutex<BinaryHeap<Reverse<(u64, PathBuf)>>>,
+}
+
+impl TopEntries {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            heap: Mutex::new(BinaryHeap::with_capacity(
+                limit.saturating_add(1).min(PREALLOCATED),
+            )),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    /// 记录一个条目，超出数量时丢弃最小的
+    pub fn offer(&self, size: u64, path: PathBuf) {
+        if !self.is_enabled() {
+            return;
+        }
+        let mut heap = self.heap.lock().unwrap();
+        // 大小相同时按路径取舍，使结果不受并行顺序影响
+        let smallest = heap.peek().map(|Reverse((size, path))| (*size, path));
+        if heap.len() == self.limit && smallest.is_some_and(|min| min >= (size, &path)) {
+            return;
+        }
+        heap.push(Reverse((size, path)));
+        if heap.len() > self.limit {
+            heap.pop();
+        }
+    }
+
+    /// 按大小降序排列的条目
+    pub fn sorted(&self) -> Vec<(u64, PathBuf)> {
+        let heap = self.heap.lock().unwrap();
+        let mut entries = heap
+            .iter()
+            .map(|Reverse(entry)| entry.clone())
+            .collect::<Vec<_>>();
+        entries.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+        entries
+    }
+}
@@ -0,0 +1,20 @@
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+/// 结构体，记录并行扫描过程中已进入过的目录 (dev, ino)，
+/// 多个符号链接或绑定挂载指向同一目录时只计数一次
+#[derive(Debug, Default)]
+pub struct VisitedDirs {
+    seen: Mutex<HashSet<(u64, u64)>>,
+}
+
+impl VisitedDirs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录进入该目录，已进入过则返回 false
+    pub fn first_visit(&self, volume_id: u64, inode: u64) -> bool {
+        self.seen.lock().unwrap().insert((volume_id, inode))
+    }
+}
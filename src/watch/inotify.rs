@@ -0,0 +1,131 @@
+use std::collections::HashMap;
+use std::ffi::{CString, OsString};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// 条目的增删、改名与内容变化
+const WATCH_MASK: u32 = libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_MODIFY
+    | libc::IN_MOVED_FROM
+    | libc::IN_MOVED_TO
+    | libc::IN_ONLYDIR;
+
+/// struct inotify_event { int wd; uint32_t mask; uint32_t cookie; uint32_t len; char name[]; }
+const EVENT_HEADER: usize = 16;
+
+/// 枚举，一批 inotify 事件的结果
+#[derive(Debug, PartialEq, Eq)]
+pub enum Changes {
+    /// 发生变化的条目
+    Paths(Vec<PathBuf>),
+    /// 事件队列溢出，部分变化已丢失
+    Overflow,
+}
+
+/// 结构体，监视一组目录中直接包含的条目
+#[derive(Debug)]
+pub struct Inotify {
+    fd: OwnedFd,
+    /// 监视描述符对应的目录
+    dirs: HashMap<i32, PathBuf>,
+}
+
+impl Inotify {
+    pub fn new() -> io::Result<Self> {
+        // SAFETY: 成功时返回的文件描述符归调用者所有
+        let fd = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            // SAFETY: fd 刚由 inotify_init1 返回且未被其他对象持有
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            dirs: HashMap::new(),
+        })
+    }
+
+    /// 监视目录，同一目录再次加入时更新其路径
+    pub fn watch(&mut self, dir: &Path) -> io::Result<()> {
+        let path = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+        // SAFETY: path 为以 NUL 结尾的字符串
+        let wd = unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), path.as_ptr(), WATCH_MASK) };
+        if wd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        self.dirs.insert(wd, dir.to_path_buf());
+        Ok(())
+    }
+
+    pub fn watched_count(&self) -> usize {
+        self.dirs.len()
+    }
+
+    /// 最多等待 timeout，读取已到达的全部事件
+    pub fn read_changes(&mut self, timeout: Duration) -> io::Result<Changes> {
+        let mut poll = libc::pollfd {
+            fd: self.fd.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let timeout = timeout.as_millis().min(i32::MAX as u128) as i32;
+        // SAFETY: 只传入一个 pollfd
+        if unsafe { libc::poll(&mut poll, 1, timeout) } < 0 {
+            let error = io::Error::last_os_error();
+            // 被 Ctrl-C 打断时由调用者检查中断标志
+            return match error.kind() {
+                io::ErrorKind::Interrupted => Ok(Changes::Paths(Vec::new())),
+                _ => Err(error),
+            };
+        }
+
+        let mut paths = Vec::new();
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            // SAFETY: 内核最多写入 buffer.len() 字节
+            let read = unsafe {
+                libc::read(
+                    self.fd.as_raw_fd(),
+                    buffer.as_mut_ptr().cast(),
+                    buffer.len(),
+                )
+            };
+            if read < 0 {
+                let error = io::Error::last_os_error();
+                return match error.kind() {
+                    io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted => {
+                        Ok(Changes::Paths(paths))
+                    }
+                    _ => Err(error),
+                };
+            }
+            let mut offset = 0;
+            while offset + EVENT_HEADER <= read as usize {
+                let field = |at: usize| {
+                    let bytes = &buffer[offset + at..offset + at + 4];
+                    u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+                };
+                let (wd, mask, length) = (field(0) as i32, field(4), field(12) as usize);
+                let name = &buffer[offset + EVENT_HEADER..offset + EVENT_HEADER + length];
+                // 名称以 NUL 补齐
+                let name = &name[..name.iter().position(|&byte| byte == 0).unwrap_or(length)];
+                offset += EVENT_HEADER + length;
+
+                if mask & libc::IN_Q_OVERFLOW != 0 {
+                    return Ok(Changes::Overflow);
+                }
+                if mask & libc::IN_IGNORED != 0 {
+                    self.dirs.remove(&wd);
+                    continue;
+                }
+                if let (Some(dir), false) = (self.dirs.get(&wd), name.is_empty()) {
+                    paths.push(dir.join(OsString::from_vec(name.to_vec())));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,325 @@
+#[cfg(target_os = "linux")]
+pub mod inotify;
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use termcolor::{Buffer, ColorSpec, WriteColor};
+
+use crate::methods::{convert_to_bytes, format_local_time, show_disk_analyze_result};
+use crate::struct_define::analysis_context::AnalysisContext;
+use crate::struct_define::analysis_item::AnalysisItem;
+use crate::struct_define::config::{Arguments, SymlinkPolicy};
+use crate::struct_define::display_color::COLOR_GRAY;
+use crate::struct_define::display_info::DisplayItemInfo;
+use crate::struct_define::file_info::FileInfo;
+use crate::struct_define::hard_link::HardLinkTracker;
+use crate::struct_define::visited_dirs::VisitedDirs;
+
+/// 结构体，--watch 中随文件系统变化更新的扫描结果，与事件的来源及终端的绘制分离
+pub struct LiveTree<'a> {
+    pub tree: AnalysisItem,
+    root: PathBuf,
+    root_dev: u64,
+    /// 重新读取条目时使用，不含 --cache
+    context: AnalysisContext,
+    config: &'a Arguments,
+    /// 启动以来每个被重新读取的条目的大小变化
+    growth: HashMap<PathBuf, i64>,
+}
+
+impl<'a> LiveTree<'a> {
+    /// context 为扫描 tree 时使用的上下文
+    pub fn new(
+        tree: AnalysisItem,
+        root: &Path,
+        mut context: AnalysisContext,
+        config: &'a Arguments,
+    ) -> Self {
+        // 缓存已在扫描结束时写入，重新读取的目录总是来自文件系统
+        context.cache = None;
+        Self {
+            tree,
+            root: root.to_path_buf(),
+            root_dev: context.root_dev,
+            context,
+            config,
+            growth: HashMap::new(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// 树中所有已读取的目录，即需要监视的目录
+    pub fn dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        collect_dirs(&self.tree, &self.root, &mut dirs);
+        dirs
+    }
+
+    /// 重新读取发生变化的条目，更新其节点与所有上级目录的大小。
+    /// 返回新读取的目录，它们需要加入监视
+    pub fn refresh(&mut self, changed: &[PathBuf]) -> Vec<PathBuf> {
+        let mut targets = changed
+            .iter()
+            .filter_map(|path| self.refresh_target(path))
+            .collect::<Vec<_>>();
+        targets.sort();
+        targets.dedup();
+        // 上级目录会被整体重新读取
+        let mut kept: Vec<PathBuf> = Vec::with_capacity(targets.len());
+        for target in targets {
+            if !kept.last().is_some_and(|last| target.starts_with(last)) {
+                kept.push(target);
+            }
+        }
+
+        let mut new_dirs = Vec::new();
+        for target in kept {
+            let new = self.analyze(&target);
+            if let Some(item) = &new {
+                collect_dirs(item, &target, &mut new_dirs);
+            }
+            let size_mode = self.config.size_mode();
+            let new_size = new.as_ref().map_or(0, |item| item.size(size_mode));
+            let old_size = match target == self.root {
+                // 事件队列溢出后整体重新扫描
+                true => {
+                    let old = self.tree.size(size_mode);
+                    if let Some(new) = new {
+                        self.tree = new;
+                    }
+                    old
+                }
+                false => {
+                    let relative = target.strip_prefix(&self.root).unwrap_or(&target);
+                    let names = relative.iter().collect::<Vec<_>>();
+                    self.tree
+                        .replace_descendant(&names, new, size_mode)
+                        .map_or(0, |old| old.size(size_mode))
+                }
+            };
+            let delta = new_size as i64 - old_size as i64;
+            if delta != 0 {
+                *self.growth.entry(target).or_default() += delta;
+            }
+        }
+        new_dirs
+    }
+
+    /// 需要重新读取的条目：上级目录尚不在树中时（如刚创建的目录）改为读取最近的未知目录
+    fn refresh_target(&self, path: &Path) -> Option<PathBuf> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let mut target = self.root.clone();
+        let mut item = &self.tree;
+        for component in relative.components() {
+            let Component::Normal(name) = component else {
+                return None;
+            };
+            target.push(name);
+            match item
+                .children
+                .iter()
+                .flatten()
+                .find(|child| child.name == name)
+            {
+                Some(child) if child.is_dir() => item = child,
+                _ => return Some(target),
+            }
+        }
+        Some(target)
+    }
+
+    /// 与完整扫描一样读取一个条目，被排除或已不存在时返回 None
+    fn analyze(&mut self, path: &Path) -> Option<AnalysisItem> {
+        let metadata = fs::symlink_metadata(path).ok()?;
+        if metadata.file_type().is_symlink() && self.config.symlink_policy() == SymlinkPolicy::Skip
+        {
+            return None;
+        }
+        let context = &mut self.context;
+        context.root_dev = self.root_dev;
+        if path != self.root {
+            let relative = path.strip_prefix(&self.root).unwrap_or(path);
+            let name = Path::new(path.file_name().unwrap_or(OsStr::new("")));
+            if context
+                .filter
+                .is_excluded(name, relative, metadata.is_dir())
+            {
+                return None;
+            }
+            // 以上级目录的设备识别挂载点，位于已进入的其他文件系统中的目录不是挂载点
+            if let Some(FileInfo::Directory { volume_id, .. }) = path
+                .parent()
+                .and_then(|parent| FileInfo::from_path(parent, true).ok())
+            {
+                context.root_dev = volume_id;
+            }
+        }
+        // 每次重新读取前清空，已计数的硬链接与已进入的目录不会被当作重复
+        context.hard_links = HardLinkTracker::new();
+        context.visited_dirs = VisitedDirs::new();
+        AnalysisItem::analyze(path, context).ok()
+    }
+
+    /// 启动以来增长最多的条目，按增长量降序
+    pub fn top_growers(&self, count: usize) -> Vec<(u64, &Path)> {
+        let mut growers = self
+            .growth
+            .iter()
+            .filter(|(_, &delta)| delta > 0)
+            .map(|(path, &delta)| (delta as u64, path.as_path()))
+            .collect::<Vec<_>>();
+        growers.sort_unstable_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+        growers.truncate(count);
+        growers
+    }
+
+    /// 绘制一帧：当前的树形结构，以及高亮显示的增长最多的条目
+    pub fn render(&self, watched: usize, buffer: &mut Buffer) -> io::Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        writeln!(
+            buffer,
+            "\nWatching: {} ({} directories, updated {})",
+            self.root.display(),
+            watched,
+            format_local_time(now)
+        )?;
+        show_disk_analyze_result(&self.tree, self.config, &DisplayItemInfo::new(), buffer)?;
+
+        let growers = self.top_growers(TOP_GROWERS);
+        if growers.is_empty() {
+            buffer.set_color(ColorSpec::new().set_fg(COLOR_GRAY))?;
+            writeln!(buffer, "\nNo growth since start")?;
+            buffer.reset()?;
+            return Ok(());
+        }
+        writeln!(buffer, "\nTop growers since start:")?;
+        let total = growers.iter().map(|&(delta, _)| delta).sum::<u64>();
+        for (rank, &(delta, path)) in growers.iter().enumerate() {
+            // 颜色深浅与 diff 相同，取决于占总增长的比例
+            let share = 100.0 * delta as f64 / total as f64;
+            let info = DisplayItemInfo::new().add_item(share, true);
+            buffer.set_color(
+                ColorSpec::new()
+                    .set_fg(info.display_growth_color(delta as i64))
+                    .set_bold(true),
+            )?;
+            write!(
+                buffer,
+                "{:>4}. [+{}] ",
+                rank + 1,
+                convert_to_bytes(delta as f64)
+            )?;
+            buffer.reset()?;
+            writeln!(buffer, "{}", path.display())?;
+        }
+        Ok(())
+    }
+}
+
+/// 每帧显示的增长最多的条目数
+const TOP_GROWERS: usize = 5;
+
+/// 函数，收集已读取的目录，未扫描的挂载点与未跟随的符号链接除外
+fn collect_dirs(item: &AnalysisItem, path: &Path, dirs: &mut Vec<PathBuf>) {
+    let Some(children) = &item.children else {
+        return;
+    };
+    let unscanned = item.mount.as_ref().is_some_and(|mount| !mount.scanned);
+    let skipped = item
+        .symlink
+        .as_ref()
+        .is_some_and(|symlink| symlink.cycle || symlink.duplicate);
+    if unscanned || skipped {
+        return;
+    }
+    dirs.push(path.to_path_buf());
+    for child in children {
+        collect_dirs(child, &path.join(&child.name), dirs);
+    }
+}
+
+/// 函数，监视扫描的目录，每隔 --interval 秒按文件系统的变化重新绘制，直到按下 Ctrl-C
+#[cfg(target_os = "linux")]
+pub fn run_watch(
+    mut live: LiveTree,
+    interrupt: &crate::struct_define::interrupt::Interrupt,
+    color: termcolor::ColorChoice,
+) -> Result<(), Box<dyn Error>> {
+    use crossterm::cursor::MoveTo;
+    use crossterm::execute;
+    use crossterm::terminal::{Clear, ClearType};
+    use inotify::{Changes, Inotify};
+    use std::time::{Duration, Instant};
+    use termcolor::BufferWriter;
+
+    let interval = Duration::from_secs(live.config.interval.unwrap_or(2).max(1));
+    let mut inotify = Inotify::new()?;
+    let watch = |inotify: &mut Inotify, dirs: Vec<PathBuf>| {
+        let failed = dirs
+            .iter()
+            .filter(|dir| inotify.watch(dir).is_err())
+            .count();
+        if failed > 0 {
+            eprintln!(
+                "mrdu: could not watch {} directories (see fs.inotify.max_user_watches)",
+                failed
+            );
+        }
+    };
+    watch(&mut inotify, live.dirs());
+
+    // 在终端中原地重绘，否则只在有变化时输出新的一帧
+    let terminal = color != termcolor::ColorChoice::Never;
+    let stdout = BufferWriter::stdout(color);
+    let draw = |live: &LiveTree, watched: usize| -> io::Result<()> {
+        let mut buffer = stdout.buffer();
+        live.render(watched, &mut buffer)?;
+        if terminal {
+            execute!(io::stdout(), Clear(ClearType::All), MoveTo(0, 0))?;
+        }
+        stdout.print(&buffer)
+    };
+    draw(&live, inotify.watched_count())?;
+
+    while !interrupt.is_triggered() {
+        let deadline = Instant::now() + interval;
+        let mut changed = Vec::new();
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() || interrupt.is_triggered() {
+                break;
+            }
+            // 分段等待，及时响应 Ctrl-C
+            match inotify.read_changes(remaining.min(Duration::from_millis(200)))? {
+                Changes::Paths(paths) => changed.extend(paths),
+                Changes::Overflow => changed.push(live.root().to_path_buf()),
+            }
+        }
+        if changed.is_empty() && !terminal {
+            continue;
+        }
+        let new_dirs = live.refresh(&changed);
+        watch(&mut inotify, new_dirs);
+        draw(&live, inotify.watched_count())?;
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn run_watch(
+    _live: LiveTree,
+    _interrupt: &crate::struct_define::interrupt::Interrupt,
+    _color: termcolor::ColorChoice,
+) -> Result<(), Box<dyn Error>> {
+    Err("--watch is only supported on Linux".into())
+}
@@ -2,6 +2,7 @@ use assert_cmd::Command;
 use std::error::Error;
 use std::ffi::OsStr;
 use std::fs;
+use std::path::{Path, PathBuf};
 use std::str;
 use walkdir::WalkDir;
 
@@ -57,13 +58,26 @@ pub fn get_max_depth(path: &str) -> Option<usize> {
     max_depth
 }
 
+// 在 cargo 提供的临时目录下新建一个空的测试目录
+pub fn create_temp_dir(name: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(name);
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
 #[cfg(test)]
 mod test_analyse {
     use crate::build_command;
+    use crate::create_temp_dir;
     // use crate::get_all_filename_dirname;
     use crate::get_max_depth;
     use std::env::current_dir;
     use std::error::Error;
+    use std::ffi::OsStr;
+    use std::fs;
 
     #[test]
     // 测试没有任何参数的分析结果
@@ -178,4 +192,1143 @@ mod test_analyse {
         assert!(output.contains(" ── "));
         Ok(())
     }
+
+    #[test]
+    // 测试同一文件的多个硬链接只计数一次
+    fn test_hard_link_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("hard_link")?;
+        fs::write(dir.join("origin"), vec![0u8; 10_000])?;
+        fs::create_dir(dir.join("links"))?;
+        for i in 0..3 {
+            fs::hard_link(dir.join("origin"), dir.join("links").join(i.to_string()))?;
+        }
+        let output = build_command(vec![OsStr::new("-a"), dir.as_os_str()]);
+        assert!(output.contains("100.00% [10 KB] ── hard_link"));
+        assert!(output.contains("Hard links: 3 duplicate entries (30 KB) counted only once"));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    // 测试无法读取的目录会被计入错误汇总，而不是被静默忽略
+    fn test_unreadable_entry_analyse() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = create_temp_dir("unreadable")?;
+        fs::write(dir.join("readable"), vec![0u8; 1_000])?;
+        fs::create_dir(dir.join("locked"))?;
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o000))?;
+        // 以 root 身份运行时权限不生效，跳过该测试
+        if fs::read_dir(dir.join("locked")).is_ok() {
+            return Ok(());
+        }
+        let output = build_command(vec![dir.as_os_str()]);
+        fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o755))?;
+        assert!(output.contains("1 entries could not be read (use --show-errors to list them)"));
+        Ok(())
+    }
+
+    #[test]
+    // 测试 both 模式同时显示实际占用与文件长度，稀疏文件不占用磁盘块
+    fn test_size_mode_both_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("size_mode")?;
+        fs::write(dir.join("dense"), vec![1u8; 10_000])?;
+        fs::File::create(dir.join("sparse"))?.set_len(1_000_000)?;
+        let output = build_command(vec![
+            OsStr::new("--size-mode"),
+            OsStr::new("both"),
+            dir.as_os_str(),
+        ]);
+        assert!(output.contains("Sizes: [allocated | apparent]"));
+        assert!(output.contains(" | 1.01 MB] ── size_mode"));
+        assert!(output.contains(" | 10 KB] ── dense"));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    // 测试挂载点默认作为未扫描的叶子节点显示，而不是从结果中消失
+    fn test_mount_point_analyse() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::MetadataExt;
+
+        // 仅当 /dev/shm 是独立挂载的文件系统时测试
+        if fs::metadata("/dev/shm")?.dev() == fs::metadata("/dev")?.dev() {
+            return Ok(());
+        }
+        let output = build_command(vec!["-d", "1", "/dev"]);
+        assert!(output.contains("── shm [mount: "));
+        assert!(output.contains(", not scanned]"));
+        let output = build_command(vec!["-d", "1", "--cross-filesystems", "/dev"]);
+        assert!(output.contains("── shm [mount: "));
+        assert!(!output.contains(", not scanned]"));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    // 测试跟随符号链接时分析其目标，并在链接指回祖先目录时停止
+    fn test_follow_symlinks_analyse() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::symlink;
+
+        let dir = create_temp_dir("follow_symlinks")?;
+        fs::create_dir_all(dir.join("real").join("sub"))?;
+        fs::write(dir.join("real").join("sub").join("data"), vec![1u8; 10_000])?;
+        fs::create_dir(dir.join("farm"))?;
+        symlink("../real", dir.join("farm").join("link"))?;
+        symlink("..", dir.join("real").join("sub").join("up"))?;
+
+        let output = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("-d"),
+            OsStr::new("5"),
+            OsStr::new("-p=-1"),
+            OsStr::new("--follow-symlinks"),
+            dir.as_os_str(),
+        ]);
+        // real 与 farm/link 为同一目录，只计数一次
+        assert!(output.contains("100.00% [10 KB] ── follow_symlinks"));
+        assert!(output.contains("── up -> .. (cycle, not followed)"));
+
+        let output = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("--symlinks"),
+            OsStr::new("skip"),
+            dir.as_os_str(),
+        ]);
+        assert!(output.contains("100.00% [10 KB] ── follow_symlinks"));
+        assert!(!output.contains("->"));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    // 测试多个符号链接指向同一目录时只跟随并计数一次
+    fn test_shared_symlink_target_analyse() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::symlink;
+
+        let base = create_temp_dir("shared_symlink_target")?;
+        fs::create_dir(base.join("target"))?;
+        fs::write(base.join("target").join("data"), vec![1u8; 100_000])?;
+        let dir = base.join("scan");
+        fs::create_dir(&dir)?;
+        for name in ["first", "second", "third"] {
+            symlink("../target", dir.join(name))?;
+        }
+
+        let output = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("-L"),
+            OsStr::new("-p=-1"),
+            dir.as_os_str(),
+        ]);
+        assert!(output.contains("100.00% [100 KB] ── scan"));
+        assert_eq!(output.matches("(already counted, not followed)").count(), 2);
+        Ok(())
+    }
+
+    #[test]
+    // 测试 --exclude 跳过的目录不会出现在结果中，并统计被排除的条目数
+    fn test_exclude_include_analyse() -> Result<(), Box<dyn Error>> {
+        let output = build_command(vec![
+            "-a",
+            "--exclude",
+            "test_dir_*",
+            "--exclude",
+            "*.c",
+            "tests/test_file",
+        ]);
+        assert!(output.contains("100.00% [5.73 KB] ── test_file"));
+        assert!(!output.contains("test_dir_"));
+        assert!(output.contains("4 entries excluded"));
+
+        let output = build_command(vec!["-a", "-d", "5", "--include", "*.c", "tests/test_file"]);
+        assert!(output.contains("100.00% [2.27 KB] ── test_file"));
+        assert!(output.contains("── test_file_d3.c"));
+        assert!(!output.contains("── test_file_d1\n"));
+        Ok(())
+    }
+
+    #[test]
+    // 测试 --respect-gitignore 跳过被忽略的条目，--only-ignored 只统计被忽略的条目
+    fn test_gitignore_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("gitignore")?;
+        fs::create_dir_all(dir.join(".git").join("info"))?;
+        fs::create_dir_all(dir.join("src"))?;
+        fs::create_dir_all(dir.join("build"))?;
+        fs::write(dir.join(".gitignore"), "build/\n*.log\n")?;
+        fs::write(dir.join(".git").join("info").join("exclude"), "secret\n")?;
+        fs::write(dir.join("src").join(".ignore"), "!keep.log\n")?;
+        fs::write(dir.join("src").join("main.rs"), vec![0u8; 4_000])?;
+        fs::write(dir.join("src").join("keep.log"), vec![0u8; 1_000])?;
+        fs::write(dir.join("src").join("debug.log"), vec![0u8; 2_000])?;
+        fs::write(dir.join("build").join("bin"), vec![0u8; 10_000])?;
+        fs::write(dir.join("secret"), vec![0u8; 3_000])?;
+
+        let output = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("--exclude"),
+            OsStr::new(".git*"),
+            OsStr::new("--exclude"),
+            OsStr::new(".ignore"),
+            OsStr::new("--respect-gitignore"),
+            dir.as_os_str(),
+        ]);
+        assert!(output.contains("100.00% [5 KB] ── gitignore"));
+        assert!(output.contains("3 entries ignored by .gitignore rules"));
+
+        let output = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("--exclude"),
+            OsStr::new(".git*"),
+            OsStr::new("--only-ignored"),
+            dir.as_os_str(),
+        ]);
+        assert!(output.contains("100.00% [15 KB] ── gitignore"));
+        assert!(output.contains("── debug.log"));
+        assert!(!output.contains("── keep.log"));
+        Ok(())
+    }
+
+    #[test]
+    // 测试 JSON 输出的结构，以及 --full 忽略 --max-depth 与 --min-percent
+    fn test_json_output_analyse() -> Result<(), Box<dyn Error>> {
+        let output = build_command(vec!["-o", "json", "-d", "1", "tests/test_file"]);
+        let json: serde_json::Value = serde_json::from_str(&output)?;
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["root"], "tests/test_file");
+        let tree = &json["tree"];
+        assert_eq!(tree["kind"], "dir");
+        assert_eq!(tree["path"], "tests/test_file");
+        assert_eq!(tree["apparent_size"], 25624);
+        assert_eq!(tree["file_count"], 11);
+        assert_eq!(tree["dir_count"], 5);
+        let children = tree["children"].as_array().unwrap();
+        assert!(children.iter().all(|child| child.get("children").is_none()));
+        assert!(children
+            .iter()
+            .any(|child| child["path"] == "tests/test_file/test_dir_d2"));
+
+        let output = build_command(vec!["-o", "json", "--full", "tests/test_file"]);
+        let json: serde_json::Value = serde_json::from_str(&output)?;
+        let children = json["tree"]["children"].as_array().unwrap();
+        assert_eq!(children.len(), 7);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    // 测试 CSV 与 TSV 对特殊字符与非 UTF-8 文件名的转义
+    fn test_delimited_output_analyse() -> Result<(), Box<dyn Error>> {
+        use assert_cmd::Command;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = create_temp_dir("delimited")?;
+        fs::write(dir.join("a,\"b\""), vec![0u8; 4_000])?;
+        fs::write(dir.join("tab\tnew\nline"), vec![0u8; 3_000])?;
+        fs::write(dir.join(OsStr::from_bytes(b"bad\xff")), vec![0u8; 2_000])?;
+        let run = |format: &str| {
+            let output = Command::cargo_bin("mrdu")
+                .unwrap()
+                .args(["-a", "-o", format])
+                .arg(&dir)
+                .unwrap();
+            output.stdout
+        };
+        let contains = |haystack: &[u8], needle: &[u8]| {
+            haystack.windows(needle.len()).any(|window| window == needle)
+        };
+
+        let csv = run("csv");
+        assert!(csv.starts_with(
+            b"path,depth,kind,size,percent_of_parent,percent_of_root,child_count\n"
+        ));
+        assert!(contains(&csv, b"/delimited/a,\"\"b\"\"\",1,file,4000,44.44,44.44,0\n"));
+        assert!(contains(&csv, b"/delimited/tab\tnew\nline\",1,file,3000,"));
+        assert!(contains(&csv, b"/delimited/bad\xff,1,file,2000,"));
+
+        let tsv = run("tsv");
+        assert!(contains(&tsv, b"/delimited/a,\"b\"\t1\tfile\t4000\t"));
+        assert!(contains(&tsv, b"/delimited/tab\\tnew\\nline\t1\tfile\t3000\t"));
+        assert!(contains(&tsv, b"/delimited/bad\xff\t1\tfile\t2000\t"));
+        Ok(())
+    }
+
+    #[test]
+    // 测试导出为 ncdu 格式后再导入，显示的结果与直接扫描一致
+    fn test_ncdu_export_import_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("ncdu")?;
+        let export = dir.join("export.json");
+        let scanned = build_command(vec![
+            OsStr::new("--export-ncdu"),
+            export.as_os_str(),
+            OsStr::new("tests/test_file"),
+        ]);
+        let imported = build_command(vec![OsStr::new("--import-ncdu"), export.as_os_str()]);
+        let tree = |output: &str| {
+            output
+                .lines()
+                .filter(|line| line.contains("──"))
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        assert!(imported.contains("Imported: "));
+        assert_eq!(tree(&scanned), tree(&imported));
+
+        // ncdu 写出的文件：被排除的其他文件系统、读取错误
+        fs::write(
+            &export,
+            r#"[1,2,{"progname":"ncdu","progver":"1.19"},
+            [{"name":"/data","asize":4096},
+              {"name":"a","asize":3000,"dsize":4096},
+              [{"name":"locked","read_error":true},{"name":"b","asize":1000,"dsize":4096}],
+              {"name":"cache","excluded":"pattern"},
+              {"name":"mnt","excluded":"otherfs"}]]"#,
+        )?;
+        let imported = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("--import-ncdu"),
+            export.as_os_str(),
+        ]);
+        assert!(imported.contains("Imported: /data"));
+        assert!(imported.contains("100.00% [4 KB] ── data"));
+        assert!(imported.contains("75.00% [3 KB] ── a"));
+        assert!(imported.contains("── mnt [mount: other filesystem, not scanned]"));
+        assert!(!imported.contains("cache"));
+        assert!(imported.contains("1 entries could not be read"));
+
+        // 同一设备上 inode 相同的硬链接只计数一次，不同设备上的 inode 互不相关
+        fs::write(
+            &export,
+            r#"[1,2,{"progname":"ncdu","progver":"1.19"},
+            [{"name":"/data","dev":1},
+              {"name":"a","asize":3000,"ino":7,"nlink":2,"hlnkc":true},
+              [{"name":"sub"},{"name":"b","asize":3000,"ino":7,"nlink":2,"hlnkc":true}],
+              [{"name":"other","dev":2},{"name":"c","asize":1000,"ino":7,"hlnkc":true}]]]"#,
+        )?;
+        let imported = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("--import-ncdu"),
+            export.as_os_str(),
+        ]);
+        assert!(imported.contains("100.00% [4 KB] ── data"));
+
+        // 超过 JSON 默认嵌套层数的目录树导出后也能导入
+        let deep = dir.join("deep");
+        let leaf = deep.join(["d"; 300].join("/"));
+        fs::create_dir_all(&leaf)?;
+        fs::write(leaf.join("file"), vec![0u8; 3_000])?;
+        build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("--export-ncdu"),
+            export.as_os_str(),
+            deep.as_os_str(),
+        ]);
+        let imported = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("--import-ncdu"),
+            export.as_os_str(),
+        ]);
+        assert!(imported.contains("100.00% [3 KB] ── deep"));
+        Ok(())
+    }
+
+    #[test]
+    // 测试保存快照后与另一个快照及重新扫描的结果比较
+    fn test_snapshot_diff_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("snapshot")?;
+        let data = dir.join("data");
+        fs::create_dir_all(data.join("logs"))?;
+        fs::write(data.join("logs/a.log"), vec![0u8; 5_000])?;
+        fs::write(data.join("old"), vec![0u8; 1_000])?;
+        fs::write(data.join("same"), vec![0u8; 2_000])?;
+        let (old, new) = (dir.join("old.json"), dir.join("new.json"));
+        let save = |file: &std::path::Path| {
+            build_command(vec![
+                OsStr::new("-a"),
+                data.as_os_str(),
+                OsStr::new("snapshot"),
+                OsStr::new("save"),
+                file.as_os_str(),
+            ])
+        };
+        assert!(save(&old).starts_with("Saved snapshot of "));
+
+        fs::remove_file(data.join("old"))?;
+        fs::write(data.join("logs/b.log"), vec![0u8; 20_000])?;
+        fs::write(data.join("logs/a.log"), vec![0u8; 4_000])?;
+        save(&new);
+        let diff = |new: Option<&std::path::Path>| {
+            let mut args = vec![OsStr::new("-a"), OsStr::new("diff"), old.as_os_str()];
+            args.extend(new.map(|new| new.as_os_str()));
+            build_command(args)
+        };
+
+        let output = diff(Some(&new));
+        assert!(output.contains("└── +18 KB [8 KB -> 26 KB] ── data (grown)"));
+        assert!(output.contains("├── +19 KB [5 KB -> 24 KB] ── logs (grown)"));
+        assert!(output.contains("│  ├── +20 KB [0 B -> 20 KB] ── b.log (added)"));
+        assert!(output.contains("│  └── -1 KB [5 KB -> 4 KB] ── a.log (shrunk)"));
+        assert!(output.contains("└── -1 KB [1 KB -> 0 B] ── old (removed)"));
+        assert!(!output.contains("same"));
+
+        // 不指定新快照时重新扫描
+        fs::remove_dir_all(data.join("logs"))?;
+        let output = diff(None);
+        assert!(output.contains("(now)"));
+        assert!(output.contains("-5 KB [5 KB -> 0 B] ── logs (removed)"));
+
+        // 超过 JSON 默认嵌套层数的目录树也能读回
+        let deep = dir.join("deep");
+        let leaf = deep.join(["d"; 300].join("/"));
+        fs::create_dir_all(&leaf)?;
+        fs::write(leaf.join("file"), vec![0u8; 3_000])?;
+        let snapshot = dir.join("deep.json");
+        build_command(vec![
+            OsStr::new("-a"),
+            deep.as_os_str(),
+            OsStr::new("snapshot"),
+            OsStr::new("save"),
+            snapshot.as_os_str(),
+        ]);
+        fs::write(leaf.join("file"), vec![0u8; 5_000])?;
+        let output = build_command(vec![OsStr::new("-a"), OsStr::new("diff"), snapshot.as_os_str()]);
+        assert!(output.contains("└── +2 KB [3 KB -> 5 KB] ── deep (grown)"));
+        Ok(())
+    }
+
+    #[test]
+    // 测试最大文件与直接内容最大的目录的排名
+    fn test_top_entries_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("top")?;
+        fs::create_dir_all(dir.join("a/b/c"))?;
+        fs::write(dir.join("a/b/c/deep"), vec![0u8; 9_000])?;
+        fs::write(dir.join("a/one"), vec![0u8; 3_000])?;
+        fs::write(dir.join("a/two"), vec![0u8; 3_000])?;
+        fs::write(dir.join("a/b/small"), vec![0u8; 1_000])?;
+        fs::write(dir.join("root"), vec![0u8; 5_000])?;
+        let output = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("--top-files"),
+            OsStr::new("2"),
+            OsStr::new("--top-dirs"),
+            OsStr::new("3"),
+            dir.as_os_str(),
+        ]);
+        let path = |relative: &str| dir.join(relative).display().to_string();
+        let expected_files = format!(
+            "Largest files:\n   1. [9 KB] {}\n   2. [5 KB] {}\n",
+            path("a/b/c/deep"),
+            path("root")
+        );
+        assert!(output.contains(&expected_files));
+        let expected_dirs = format!(
+            "Largest directories by direct content:\n   1. [9 KB] {}\n   2. [6 KB] {}\n   3. [5 KB] {}\n",
+            path("a/b/c"),
+            path("a"),
+            dir.display()
+        );
+        assert!(output.contains(&expected_dirs));
+
+        // 远大于条目数的 N 不会预先分配
+        for limit in ["1000000000000", "18446744073709551615"] {
+            let output = build_command(vec![
+                OsStr::new("-a"),
+                OsStr::new("--top-files"),
+                OsStr::new(limit),
+                OsStr::new("--top-dirs"),
+                OsStr::new(limit),
+                dir.as_os_str(),
+            ]);
+            assert!(output.contains(&format!("   5. [1 KB] {}\n", path("a/b/small"))));
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    // 测试按扩展名与按内容类型汇总文件，低于 --min-percent 的类型合并为一行
+    fn test_by_extension_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("by_extension")?;
+        fs::create_dir_all(dir.join("logs"))?;
+        fs::write(dir.join("logs/a.log"), "log line\n".repeat(500))?;
+        fs::write(dir.join("logs/b.LOG"), "log line\n".repeat(300))?;
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.resize(1_800, 0);
+        fs::write(dir.join("picture.dat"), png)?;
+        fs::write(dir.join("README"), vec![b'x'; 100])?;
+        let run = |mode: &str| {
+            build_command(vec![
+                OsStr::new("-a"),
+                OsStr::new("-d"),
+                OsStr::new("0"),
+                OsStr::new(mode),
+                dir.as_os_str(),
+            ])
+        };
+
+        let output = run("--by-extension");
+        assert!(output.contains("Usage by extension:\n 79.12% [7.2 KB] ── .log (2 files)\n"));
+        assert!(output.contains(" 19.78% [1.8 KB] ── .dat (1 files)\n"));
+        assert!(output.contains("  1.10% [100 B] ── 1 more below 5% (1 files)\n"));
+
+        // 识别内容时不能打开 FIFO，否则扫描会一直阻塞
+        let fifo = std::ffi::CString::new(dir.join("pipe").into_os_string().into_encoded_bytes())?;
+        assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o644) }, 0);
+        let output = run("--by-content");
+        assert!(output.contains("Usage by content type:\n 80.22% [7.3 KB] ── text (3 files)\n"));
+        assert!(output.contains(" 19.78% [1.8 KB] ── PNG image (1 files)\n"));
+        assert!(output.contains("  0.00% [0 B] ── 1 more below 5% (1 files)\n"));
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    // 测试按所有者与用户组汇总，并按顶层目录分别汇总
+    fn test_by_owner_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("by_owner")?;
+        fs::create_dir_all(dir.join("shared"))?;
+        fs::write(dir.join("shared/mine"), vec![0u8; 1_000])?;
+        fs::write(dir.join("shared/theirs"), vec![0u8; 3_000])?;
+        fs::write(dir.join("top"), vec![0u8; 2_000])?;
+        // 只有 root 能修改所有者，否则跳过该测试
+        if std::os::unix::fs::chown(dir.join("shared/theirs"), Some(65534), Some(65534)).is_err() {
+            return Ok(());
+        }
+        let output = build_command(vec![
+            OsStr::new("-a"),
+            OsStr::new("-d"),
+            OsStr::new("0"),
+            OsStr::new("--by-owner"),
+            OsStr::new("--by-group"),
+            OsStr::new("--per-directory"),
+            dir.as_os_str(),
+        ]);
+        let passwd = fs::read_to_string("/etc/passwd")?;
+        let nobody = match passwd.lines().any(|line| line.starts_with("nobody:x:65534:")) {
+            true => "nobody",
+            false => "65534",
+        };
+        assert!(output.contains(&format!(
+            "Usage by owner:\n 50.00% [3 KB] ── {} (1 files)\n 50.00% [3 KB] ── root (2 files)\n",
+            nobody
+        )));
+        assert!(output.contains(&format!(
+            "Usage by owner per top-level directory:\nshared\n   75.00% [3 KB] ── {} (1 files)\n   25.00% [1 KB] ── root (1 files)\n.\n  100.00% [2 KB] ── root (1 files)\n",
+            nobody
+        )));
+        assert!(output.contains("Usage by group:\n"));
+        Ok(())
+    }
+
+    #[test]
+    // 测试按修改时间统计年龄分布，以及 --older-than 与 --newer-than 只计入满足条件的文件
+    fn test_file_age_analyse() -> Result<(), Box<dyn Error>> {
+        use assert_cmd::Command;
+        use std::time::{Duration, SystemTime};
+
+        let dir = create_temp_dir("file_age")?;
+        fs::create_dir_all(dir.join("archive"))?;
+        let days_ago = |days: u64| SystemTime::now() - Duration::from_secs(days * 24 * 60 * 60);
+        for (name, size, days) in [("archive/cold", 6_000, 400), ("warm", 3_000, 10), ("hot", 1_000, 0)] {
+            fs::write(dir.join(name), vec![0u8; size])?;
+            fs::File::options()
+                .write(true)
+                .open(dir.join(name))?
+                .set_modified(days_ago(days))?;
+        }
+        let run = |args: &[&str]| {
+            let mut command = vec![OsStr::new("-a")];
+            command.extend(args.iter().map(OsStr::new));
+            command.push(dir.as_os_str());
+            build_command(command)
+        };
+
+        let output = run(&["--age-histogram"]);
+        assert!(output.contains(
+            "Usage by modification time:\n 10.00% [1 KB] ── < 1 day (1 files)\n  0.00% [0 B] ── < 1 week (0 files)\n 30.00% [3 KB] ── < 1 month (1 files)\n"
+        ));
+        assert!(output.contains(" 60.00% [6 KB] ── older (1 files)\n"));
+
+        let output = run(&["--older-than", "90d"]);
+        assert!(output.contains("└── 100.00% [6 KB] ── file_age"));
+        assert!(!output.contains("── warm"));
+        assert!(output.contains("2 files outside the age filter were not counted"));
+
+        let output = run(&["--newer-than", "1y", "--older-than", "1w"]);
+        assert!(output.contains("└── 100.00% [3 KB] ── file_age"));
+
+        // 超出范围的时长被拒绝，而不是溢出
+        let mut cmd = Command::cargo_bin("mrdu")?;
+        cmd.args(["--older-than", "999999999999999999d"]).arg(&dir);
+        let stderr = String::from_utf8(cmd.assert().failure().get_output().stderr.clone())?;
+        assert!(stderr.contains("age too large: 999999999999999999d"));
+        Ok(())
+    }
+    #[test]
+    // 测试查找重复文件，互为硬链接的文件不算作重复
+    fn test_dupes_analyse() -> Result<(), Box<dyn Error>> {
+        use mrdu::struct_define::analysis_context::AnalysisContext;
+        use mrdu::struct_define::analysis_item::AnalysisItem;
+        use mrdu::struct_define::config::{Arguments, IoMode};
+        use mrdu::struct_define::duplicates::find_duplicates;
+        use mrdu::struct_define::file_info::FileInfo;
+        use mrdu::struct_define::interrupt::Interrupt;
+        use structopt::StructOpt;
+
+        let dir = create_temp_dir("dupes")?;
+        fs::create_dir_all(dir.join("sub"))?;
+        let content = (0..10_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+        for name in ["a", "sub/a2", "sub/a3"] {
+            fs::write(dir.join(name), &content)?;
+        }
+        let mut changed = content.clone();
+        changed[5_000] ^= 1;
+        fs::write(dir.join("changed"), &changed)?;
+        fs::write(dir.join("linked"), vec![7u8; 2_000])?;
+        fs::hard_link(dir.join("linked"), dir.join("sub/linked"))?;
+        fs::write(dir.join("s1"), "abc")?;
+        fs::write(dir.join("s2"), "abc")?;
+
+        let output = build_command(vec![OsStr::new("dupes"), dir.as_os_str()]);
+        assert!(output.contains("Duplicates: 2 sets, 20 KB wasted"));
+        assert!(output.contains(&format!(
+            "[10 KB x 3, 20 KB wasted]\n  {0}/a\n  {0}/sub/a2\n  {0}/sub/a3\n",
+            dir.display()
+        )));
+        assert!(output.contains("[3 B x 2, 3 B wasted]"));
+        assert!(!output.contains("changed"));
+        assert!(!output.contains("linked"));
+
+        let output = build_command(vec![
+            OsStr::new("-o"),
+            OsStr::new("json"),
+            OsStr::new("dupes"),
+            OsStr::new("--min-size"),
+            OsStr::new("100"),
+            dir.as_os_str(),
+        ]);
+        let report: serde_json::Value = serde_json::from_str(&output)?;
+        assert_eq!(report["wasted"], 20_000);
+        assert_eq!(report["sets"].as_array().map(Vec::len), Some(1));
+        assert_eq!(report["sets"][0]["size"], 10_000);
+
+        // 逐个与并行读取的结果相同；中断后不再读取文件内容，未确认的文件不会被报告为重复
+        let config = Arguments::from_iter(["mrdu"]);
+        let volume_id = match FileInfo::from_path(&dir, false)? {
+            FileInfo::Directory { volume_id, .. } => volume_id,
+            _ => unreachable!(),
+        };
+        let context = AnalysisContext::new(&config, &dir, volume_id)?;
+        let tree = AnalysisItem::analyze(&dir, &context)?;
+        let interrupt = Interrupt::new();
+        let sequential = find_duplicates(&tree, &dir, 1, IoMode::Sequential, &interrupt);
+        assert_eq!(sequential.len(), 2);
+        assert_eq!(find_duplicates(&tree, &dir, 1, IoMode::Parallel, &interrupt), sequential);
+        interrupt.trigger();
+        assert!(find_duplicates(&tree, &dir, 1, IoMode::Parallel, &interrupt).is_empty());
+        Ok(())
+    }
+    #[test]
+    // 测试扫描过程中更新的进度计数，stderr 不是终端时不输出进度行
+    fn test_progress_analyse() -> Result<(), Box<dyn Error>> {
+        use mrdu::struct_define::analysis_context::AnalysisContext;
+        use mrdu::struct_define::analysis_item::AnalysisItem;
+        use mrdu::struct_define::config::Arguments;
+        use mrdu::struct_define::file_info::FileInfo;
+        use structopt::StructOpt;
+
+        let dir = create_temp_dir("progress")?;
+        fs::create_dir_all(dir.join("sub"))?;
+        fs::write(dir.join("a"), vec![0u8; 1_000])?;
+        fs::write(dir.join("sub/b"), vec![0u8; 2_000])?;
+        let volume_id = match FileInfo::from_path(&dir, false)? {
+            FileInfo::Directory { volume_id, .. } => volume_id,
+            _ => unreachable!(),
+        };
+        let config = Arguments::from_iter(["mrdu", "-a"]);
+        let context = AnalysisContext::new(&config, &dir, volume_id)?;
+        AnalysisItem::analyze(&dir, &context)?;
+
+        let progress = &context.progress;
+        assert_eq!((progress.files(), progress.dirs()), (2, 2));
+        assert_eq!((progress.bytes(), progress.errors()), (3_000, 0));
+        assert!(progress
+            .line(usize::MAX)
+            .starts_with("Scanning: 2 files, 2 dirs, 3 KB: "));
+        // 当前路径过长时省略开头
+        assert!(progress.line(40).contains(": ..."));
+        assert!(progress.line(40).chars().count() <= 40);
+
+        let output = build_command(vec![OsStr::new("-a"), dir.as_os_str()]);
+        assert!(output.contains("[3 KB] ── progress"));
+        Ok(())
+    }
+    #[test]
+    // 测试中断后停止进入新的条目，未完整读取的目录被标记为 partial
+    fn test_interrupt_analyse() -> Result<(), Box<dyn Error>> {
+        use mrdu::methods::show_disk_analyze_result;
+        use mrdu::output::json::write_json;
+        use mrdu::struct_define::analysis_context::AnalysisContext;
+        use mrdu::struct_define::analysis_item::AnalysisItem;
+        use mrdu::struct_define::config::Arguments;
+        use mrdu::struct_define::display_info::DisplayItemInfo;
+        use mrdu::struct_define::file_info::FileInfo;
+        use structopt::StructOpt;
+        use termcolor::Buffer;
+
+        let dir = create_temp_dir("interrupt")?;
+        fs::create_dir_all(dir.join("sub"))?;
+        fs::write(dir.join("sub/a"), vec![0u8; 1_000])?;
+        let volume_id = match FileInfo::from_path(&dir, false)? {
+            FileInfo::Directory { volume_id, .. } => volume_id,
+            _ => unreachable!(),
+        };
+        let config = Arguments::from_iter(["mrdu", "-a"]);
+        let context = AnalysisContext::new(&config, &dir, volume_id)?;
+        let complete = AnalysisItem::analyze(&dir, &context)?;
+        assert!(!complete.partial);
+
+        context.interrupt.trigger();
+        let partial = AnalysisItem::analyze(&dir, &context)?;
+        assert!(partial.partial);
+        assert_eq!(partial.children.as_ref().map(Vec::len), Some(0));
+
+        let mut buffer = Buffer::no_color();
+        show_disk_analyze_result(&partial, &config, &DisplayItemInfo::new(), &mut buffer)?;
+        assert!(std::str::from_utf8(buffer.as_slice())?.contains("── interrupt (partial)\n"));
+        let mut json = Vec::new();
+        write_json(&partial, &dir, &config, &mut json)?;
+        let json: serde_json::Value = serde_json::from_slice(&json)?;
+        assert_eq!(json["tree"]["partial"], true);
+        Ok(())
+    }
+    #[test]
+    // 测试 --threads 与 --io-mode 不影响结果，并在耗时后输出扫描速度
+    fn test_io_mode_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("io_mode")?;
+        for sub in ["a", "b", "c"] {
+            fs::create_dir_all(dir.join(sub).join("nested"))?;
+            fs::write(dir.join(sub).join("file"), vec![0u8; 1_000])?;
+            fs::write(dir.join(sub).join("nested/file"), vec![0u8; 2_000])?;
+        }
+        let run = |args: &[&str]| {
+            let mut command = vec![OsStr::new("-a")];
+            command.extend(args.iter().map(OsStr::new));
+            command.push(dir.as_os_str());
+            build_command(command)
+        };
+        let tree = |output: &str| {
+            output
+                .lines()
+                .filter(|line| line.contains("── "))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let parallel = run(&["--threads", "3"]);
+        assert!(parallel.contains("[9 KB] ── io_mode"));
+        assert!(parallel.contains(" (13 entries, "));
+        assert!(parallel.contains(" entries/sec, 3 threads)\n"));
+
+        let sequential = run(&["--io-mode", "sequential", "--threads", "3"]);
+        assert_eq!(tree(&sequential), tree(&parallel));
+        assert!(sequential.contains(" entries/sec, 1 threads)\n"));
+        Ok(())
+    }
+    #[test]
+    // 测试 --compact 的结果与普通扫描相同，并可合并占比很小的文件
+    fn test_compact_analyse() -> Result<(), Box<dyn Error>> {
+        use mrdu::struct_define::analysis_context::AnalysisContext;
+        use mrdu::struct_define::analysis_item::AnalysisItem;
+        use mrdu::struct_define::config::{Arguments, SizeMode};
+        use mrdu::struct_define::file_info::FileInfo;
+        use structopt::StructOpt;
+
+        let dir = create_temp_dir("compact")?;
+        fs::create_dir_all(dir.join("big/nested"))?;
+        fs::write(dir.join("big/nested/file"), vec![0u8; 10_000])?;
+        fs::write(dir.join("big/file"), vec![0u8; 5_000])?;
+        for i in 0..20 {
+            fs::write(dir.join(format!("small_{}", i)), vec![0u8; 10])?;
+        }
+        let run = |args: &[&str]| {
+            let mut command = vec![OsStr::new("-a"), OsStr::new("-p"), OsStr::new("1")];
+            command.extend(args.iter().map(OsStr::new));
+            command.push(dir.as_os_str());
+            build_command(command)
+        };
+        let tree = |output: &str| {
+            output
+                .lines()
+                .filter(|line| line.contains("── "))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let compact = run(&["--compact"]);
+        assert_eq!(tree(&compact), tree(&run(&[])));
+        assert!(compact.contains("Compact tree: 25 entries in "));
+        let folded = run(&["--compact", "--fold-small-files"]);
+        assert!(folded.contains("└──  1.32% [200 B] ── (20 smaller files)\n"));
+        assert!(folded.contains("Compact tree: 6 entries in "));
+
+        let volume_id = match FileInfo::from_path(&dir, false)? {
+            FileInfo::Directory { volume_id, .. } => volume_id,
+            _ => unreachable!(),
+        };
+        let config = Arguments::from_iter(["mrdu", "-a", "--compact"]);
+        let context = AnalysisContext::new(&config, &dir, volume_id)?;
+        let root = AnalysisItem::analyze(&dir, &context)?;
+        let tree = context.compact.unwrap().finish(root);
+        let root = tree.root();
+        assert_eq!((root.size(SizeMode::Apparent), root.file_count()), (15_200, 22));
+        let big = root.children().next().unwrap();
+        assert_eq!(big.name(), "big");
+        let nested = big.children().next().unwrap();
+        assert_eq!((nested.name(), nested.is_dir()), (OsStr::new("nested"), true));
+        assert_eq!(nested.parent().map(|parent| parent.name()), Some(OsStr::new("big")));
+        assert!(root.parent().is_none());
+        Ok(())
+    }
+    #[test]
+    #[cfg(target_os = "linux")]
+    // 测试 Linux 上通过目录文件描述符读取的结果与按完整路径读取一致
+    fn test_fast_walk_analyse() -> Result<(), Box<dyn Error>> {
+        use std::os::unix::fs::symlink;
+
+        let dir = create_temp_dir("fast_walk")?;
+        fs::create_dir_all(dir.join("a/b/c"))?;
+        fs::create_dir_all(dir.join("skipped"))?;
+        fs::write(dir.join("a/b/c/deep"), vec![0u8; 3_000])?;
+        fs::write(dir.join("a/file"), vec![0u8; 1_000])?;
+        fs::write(dir.join("skipped/file"), vec![0u8; 1_000])?;
+        symlink("a/b", dir.join("to_b"))?;
+        symlink("..", dir.join("a/b/c/loop"))?;
+        symlink("missing", dir.join("dangling"))?;
+
+        for args in [&["-a"][..], &["-L"], &["--exclude", "skipped", "--symlinks", "skip"]] {
+            let run = |extra: &[&str]| {
+                let mut command = vec![OsStr::new("-o"), OsStr::new("json"), OsStr::new("--full")];
+                command.extend(args.iter().chain(extra).map(OsStr::new));
+                command.push(dir.as_os_str());
+                build_command(command)
+            };
+            assert_eq!(run(&[]), run(&["--portable-walk"]));
+        }
+        Ok(())
+    }
+    #[test]
+    // 测试很深的目录树不会耗尽扫描线程的栈
+    fn test_deep_tree_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("deep_tree")?;
+        fs::create_dir_all(dir.join(["d"; 1500].join("/")))?;
+        for args in [&["--io-mode", "sequential"][..], &["--threads", "1"], &[]] {
+            let mut command = ["-d", "1", "-o", "json"].map(OsStr::new).to_vec();
+            command.extend(args.iter().map(OsStr::new));
+            command.push(dir.as_os_str());
+            assert!(build_command(command).contains(r#""dir_count":1500,"#));
+        }
+        Ok(())
+    }
+    #[test]
+    // 测试 --cache 复用修改时间未变的目录，--verify 重新读取所有目录
+    fn test_cache_analyse() -> Result<(), Box<dyn Error>> {
+        let dir = create_temp_dir("cache")?;
+        fs::create_dir_all(dir.join("a/nested"))?;
+        fs::create_dir_all(dir.join("b"))?;
+        fs::write(dir.join("a/nested/file"), vec![0u8; 3_000])?;
+        fs::write(dir.join("b/file"), vec![0u8; 1_000])?;
+        // 缓存文件不能放在扫描的目录中，否则每次写入都会改变目录的修改时间
+        let cache = dir.with_file_name("cache_analyse.bin");
+        let _ = fs::remove_file(&cache);
+        let run = |args: &[&str]| {
+            let mut command = vec![OsStr::new("-a"), OsStr::new("--cache"), cache.as_os_str()];
+            command.extend(args.iter().map(OsStr::new));
+            command.push(dir.as_os_str());
+            build_command(command)
+        };
+        let tree = |output: &str| {
+            output
+                .lines()
+                .filter(|line| line.contains("── "))
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let first = run(&[]);
+        assert!(first.contains("\n0 of 4 directories came from the cache\n"));
+        let second = run(&[]);
+        assert_eq!(tree(&second), tree(&first));
+        assert!(second.contains("\n4 of 4 directories came from the cache\n"));
+
+        fs::write(dir.join("b/added"), vec![0u8; 2_000])?;
+        let changed = run(&[]);
+        assert!(changed.contains("[6 KB] ── cache"));
+        assert!(changed.contains("\n3 of 4 directories came from the cache\n"));
+        let verified = run(&["--verify"]);
+        assert_eq!(tree(&verified), tree(&changed));
+        assert!(verified.contains("\n0 of 4 directories came from the cache\n"));
+
+        // 长度损坏的缓存被整体丢弃
+        let mut corrupt = fs::read(&cache)?;
+        corrupt[21..25].copy_from_slice(&u32::MAX.to_le_bytes());
+        fs::write(&cache, corrupt)?;
+        let rebuilt = run(&[]);
+        assert_eq!(tree(&rebuilt), tree(&changed));
+        assert!(rebuilt.contains("\n0 of 4 directories came from the cache\n"));
+        Ok(())
+    }
+    #[test]
+    #[cfg(target_os = "linux")]
+    // 测试 --watch 按文件系统的变化更新节点与上级目录，并列出启动以来增长最多的条目
+    fn test_watch_analyse() -> Result<(), Box<dyn Error>> {
+        use mrdu::struct_define::analysis_context::AnalysisContext;
+        use mrdu::struct_define::analysis_item::AnalysisItem;
+        use mrdu::struct_define::config::{Arguments, SizeMode};
+        use mrdu::struct_define::file_info::FileInfo;
+        use mrdu::watch::LiveTree;
+        use std::io::{BufRead, BufReader};
+        use std::process::{Command, Stdio};
+        use std::sync::mpsc;
+        use std::time::Duration;
+        use structopt::StructOpt;
+
+        let dir = create_temp_dir("watch")?;
+        fs::create_dir_all(dir.join("a"))?;
+        fs::write(dir.join("a/file"), vec![0u8; 1_000])?;
+        fs::write(dir.join("removed"), vec![0u8; 500])?;
+        let volume_id = match FileInfo::from_path(&dir, false)? {
+            FileInfo::Directory { volume_id, .. } => volume_id,
+            _ => unreachable!(),
+        };
+        let config = Arguments::from_iter(["mrdu", "-a", "--watch", "--exclude", "*.tmp"]);
+        let context = AnalysisContext::new(&config, &dir, volume_id)?;
+        let tree = AnalysisItem::analyze(&dir, &context)?;
+        let mut live = LiveTree::new(tree, &dir, context, &config);
+        assert_eq!(live.dirs(), vec![dir.clone(), dir.join("a")]);
+
+        fs::write(dir.join("a/file"), vec![0u8; 4_000])?;
+        fs::create_dir_all(dir.join("new/deep"))?;
+        fs::write(dir.join("new/deep/file"), vec![0u8; 2_000])?;
+        fs::write(dir.join("new/skipped.tmp"), vec![0u8; 9_000])?;
+        fs::remove_file(dir.join("removed"))?;
+        // 新目录中的事件在目录本身加入树之前到达时，改为读取整个新目录
+        let new_dirs = live.refresh(&[
+            dir.join("a/file"),
+            dir.join("new/deep/file"),
+            dir.join("new"),
+            dir.join("removed"),
+        ]);
+        assert_eq!(new_dirs, vec![dir.join("new"), dir.join("new/deep")]);
+        assert_eq!(live.tree.size(SizeMode::Apparent), 6_000);
+        assert_eq!((live.tree.file_count, live.tree.dir_count), (2, 3));
+        let children = live.tree.children.as_ref().unwrap();
+        assert_eq!(children[0].name, "a");
+        assert_eq!(children[1].size(SizeMode::Apparent), 2_000);
+        assert_eq!(
+            live.top_growers(5),
+            vec![
+                (3_000, dir.join("a/file").as_path()),
+                (2_000, dir.join("new").as_path())
+            ]
+        );
+
+        // 上下文在多次重新读取间复用，同一目录中的硬链接每次仍计数一次
+        fs::write(dir.join("new/linked"), vec![0u8; 1_000])?;
+        fs::hard_link(dir.join("new/linked"), dir.join("new/deep/linked"))?;
+        for _ in 0..2 {
+            live.refresh(&[dir.join("new")]);
+            assert_eq!(live.tree.size(SizeMode::Apparent), 7_000);
+        }
+
+        // 在后台监视，出现变化后输出新的一帧，Ctrl-C 后正常退出
+        let mut child = Command::new(assert_cmd::cargo::cargo_bin("mrdu"))
+            .args([
+                OsStr::new("-a"),
+                OsStr::new("--watch"),
+                OsStr::new("--interval"),
+                OsStr::new("1"),
+            ])
+            .arg(&dir)
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let (lines, received) = mpsc::channel();
+        let stdout = child.stdout.take().unwrap();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                let _ = lines.send(line);
+            }
+        });
+        let wait_for = |text: &str| loop {
+            match received.recv_timeout(Duration::from_secs(30)) {
+                Ok(line) if line.contains(text) => return true,
+                Ok(_) => {}
+                Err(_) => return false,
+            }
+        };
+        assert!(wait_for("No growth since start"));
+        fs::write(dir.join("a/grown"), vec![0u8; 7_000])?;
+        assert!(wait_for(&format!(
+            "1. [+7 KB] {}",
+            dir.join("a/grown").display()
+        )));
+        // SAFETY: 只向刚启动的子进程发送信号
+        unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGINT) };
+        assert!(child.wait()?.success());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_interactive {
+    use assert_cmd::Command;
+    use crate::create_temp_dir;
+    use mrdu::interactive::trash::Trash;
+    use mrdu::interactive::{Browser, PercentBase, Removal, SortOrder};
+    use mrdu::output::ncdu::read_ncdu;
+    use mrdu::struct_define::config::{Arguments, SizeMode};
+    use std::error::Error;
+    use std::fs;
+    use structopt::StructOpt;
+
+    #[test]
+    // 测试交互界面的展开、进入子目录、排序与百分比基准
+    fn test_interactive_browser() -> Result<(), Box<dyn Error>> {
+        let export = r#"[1,2,{"progname":"ncdu"},
+            [{"name":"/data"},
+              {"name":"b","asize":1000},
+              [{"name":"a"},{"name":"x","asize":2000},{"name":"y","asize":1000}],
+              {"name":"c","asize":500}]]"#;
+        let (root, mut tree) = read_ncdu(export.as_bytes(), SizeMode::Apparent)?;
+        let config = Arguments::from_iter(["mrdu", "-a"]);
+        let mut browser = Browser::new(&mut tree, root, &config);
+        let names = |browser: &Browser| {
+            browser
+                .rows()
+                .iter()
+                .map(|row| browser.item_at(&row.path).name.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&browser), ["a", "b", "c"]);
+
+        browser.toggle_expanded();
+        assert_eq!(names(&browser), ["a", "x", "y", "b", "c"]);
+        assert_eq!(browser.rows()[1].percent, 2000.0 / 3000.0 * 100.0);
+        browser.toggle_percent_base();
+        assert_eq!(browser.percent_base, PercentBase::Root);
+        assert_eq!(browser.rows()[1].percent, 2000.0 / 4500.0 * 100.0);
+
+        browser.cycle_sort();
+        assert_eq!(browser.sort, SortOrder::Name);
+        browser.move_selection(3);
+        assert_eq!(names(&browser)[browser.selected], "b");
+
+        browser.move_selection(-3);
+        browser.enter();
+        assert_eq!(names(&browser), ["x", "y"]);
+        assert_eq!(browser.path_of(browser.view()), std::path::Path::new("/data/a"));
+        browser.leave();
+        assert_eq!(names(&browser)[browser.selected], "a");
+        Ok(())
+    }
+
+    #[test]
+    // 测试标准输出不是终端时拒绝进入交互界面
+    fn test_interactive_requires_terminal() {
+        let output = Command::cargo_bin("mrdu")
+            .unwrap()
+            .args(["-i", "tests/test_file"])
+            .output()
+            .unwrap();
+        assert!(!output.status.success());
+        assert!(String::from_utf8_lossy(&output.stderr)
+            .contains("interactive mode requires a terminal"));
+    }
+
+    #[test]
+    // 测试删除与移入回收站后，树中各级目录的大小随之更新
+    fn test_interactive_removal() -> Result<(), Box<dyn Error>> {
+        use mrdu::struct_define::analysis_context::AnalysisContext;
+        use mrdu::struct_define::analysis_item::AnalysisItem;
+        use mrdu::struct_define::file_info::FileInfo;
+
+        let dir = create_temp_dir("removal")?;
+        let data = dir.join("data");
+        fs::create_dir_all(data.join("logs"))?;
+        fs::write(data.join("logs/old.log"), vec![0u8; 3000])?;
+        fs::write(data.join("logs/new.log"), vec![0u8; 2000])?;
+        fs::write(data.join("keep"), vec![0u8; 1000])?;
+        let scan = |args: &[&str]| -> Result<(Arguments, AnalysisItem), Box<dyn Error>> {
+            let config = Arguments::from_iter(["mrdu", "-a"].iter().chain(args));
+            let volume_id = match FileInfo::from_path(&data, false)? {
+                FileInfo::Directory { volume_id, .. } => volume_id,
+                _ => unreachable!(),
+            };
+            let context = AnalysisContext::new(&config, &data, volume_id)?;
+            let tree = AnalysisItem::analyze(&data, &context)?;
+            Ok((config, tree))
+        };
+
+        // 含有被排除的内容的目录，以及导入的树，均不允许删除
+        let (config, mut tree) = scan(&["--exclude", "old.log"])?;
+        let mut browser = Browser::new(&mut tree, data.clone(), &config);
+        browser.request_removal(Removal::Delete);
+        assert!(browser.status.as_ref().unwrap().contains("filtered out"));
+        assert!(browser.confirmation().is_none());
+        let (config, mut tree) = scan(&["--import-ncdu", "export.json"])?;
+        let mut browser = Browser::new(&mut tree, data.clone(), &config);
+        browser.request_removal(Removal::Trash);
+        assert_eq!(
+            browser.status.as_deref(),
+            Some("Removal is disabled for imported trees")
+        );
+        assert!(data.join("logs/old.log").exists());
+
+        // 被跟随的符号链接计入了目标的大小，删除时却只会删除链接本身
+        #[cfg(unix)]
+        {
+            fs::create_dir_all(dir.join("outside"))?;
+            fs::write(dir.join("outside/file"), vec![0u8; 500])?;
+            std::os::unix::fs::symlink("../outside", data.join("link"))?;
+            let (config, mut tree) = scan(&["-L"])?;
+            let mut browser = Browser::new(&mut tree, data.clone(), &config);
+            browser.move_selection(2);
+            browser.request_removal(Removal::Delete);
+            assert!(browser.status.as_ref().unwrap().contains("followed symlink"));
+            assert!(browser.confirmation().is_none());
+            fs::remove_file(data.join("link"))?;
+        }
+
+        let (config, mut tree) = scan(&[])?;
+        let mut browser = Browser::new(&mut tree, data.clone(), &config);
+        let trash = dir.join("Trash");
+        browser.trash = Some(Trash::new(trash.clone()));
+
+        // 展开 logs，选中 old.log 后移入回收站
+        browser.toggle_expanded();
+        browser.move_selection(1);
+        browser.request_removal(Removal::Trash);
+        assert!(browser.confirmation().unwrap().contains("old.log, freeing [3 KB]"));
+        assert_eq!(browser.confirm_removal()?, 3000);
+        assert!(!data.join("logs/old.log").exists());
+        assert!(trash.join("files/old.log").exists());
+        let info = fs::read_to_string(trash.join("info/old.log.trashinfo"))?;
+        assert!(info.starts_with("[Trash Info]\nPath="));
+        assert!(info.contains("/removal/data/logs/old.log\nDeletionDate="));
+
+        // 取消的操作不会删除任何内容
+        browser.request_removal(Removal::Delete);
+        browser.cancel_removal();
+        assert!(browser.confirmation().is_none());
+
+        browser.move_selection(-1);
+        browser.request_removal(Removal::Delete);
+        assert_eq!(browser.confirm_removal()?, 2000);
+        assert!(!data.join("logs").exists());
+        assert_eq!(browser.tree.apparent_size, 1000);
+        assert_eq!((browser.tree.file_count, browser.tree.dir_count), (1, 0));
+        assert_eq!(browser.rows().len(), 1);
+        assert_eq!(browser.rows()[0].percent, 100.0);
+        Ok(())
+    }
 }